@@ -17,6 +17,9 @@ echo "--- Seed Contracts Data:"
 near call $CONTRACT_ID add_collection '{"title": "Diamond", "image": "bafkreihvyvwqw6i5wfsvcaa33b7lbwqedkxo37jkaraowrkffcnvgkp74a", "stone_images": ["bafkreihvyvwqw6i5wfsvcaa33b7lbwqedkxo37jkaraowrkffcnvgkp74a", "bafkreif6s3x3ptcn47zxx4zkpazbrwphoc2cpv2vnwil7twsv3zmcns62a", "bafkreifasykvgid35csgbqmam6ywtg3hmgwed6gg3fm532cjl4mv5fz27m","bafkreiel7u7q4fxle4u6ztbkn3v224jc64axiqzl22wipvicqtbdt2btrm","bafkreifw2rdcnd3itqhhtoqsqvvjwtcprkwnu6dzokmg3rb4ailpjqufpe"]}' --accountId $NEAR_ID
 near call $CONTRACT_ID add_collection '{"title": "Igneous", "image": "bafkreiarev46fysxe2reltfzhd47j7pkw4kfkmql6nsniavkg36mo6bhhe", "stone_images": ["bafkreiarev46fysxe2reltfzhd47j7pkw4kfkmql6nsniavkg36mo6bhhe", "bafkreibysle5scntjhgcn3ejwxqiemtjwyqg6fnd4qyqg7vqmpl6kzdc34", "bafkreiehejazb5ky5oyb2k4s6ru7zaagx5zpdcvwfdz3iiwpiukwqxfdqy", "bafkreieliiumdzzjsljdxai5bti5zony66el7cx7rdltig3e2flws7dxui", "bafkreidnkl4tvp6fiwl3blwcypzransx24c3w7h7b36pyleb27uq3hh6hu", "bafkreiey2mvvsmqlhcdndalpqvwcnkx2qkqtf6illyjlr2ozm2anynot4u", "bafkreicchliwm7sfrv4zqi2pf3zgtjljbjvxvv77o6vfhb26q53v3z77aq"]}' --accountId $NEAR_ID
 
+echo "--- Sync MNL Reserve for kill rewards:"
+near call $CONTRACT_ID sync_mnl_reserve --accountId $NEAR_ID --gas 30000000000000
+
 echo "--- Mint Free Mine:"
 LAND_MINT_RESPONSE=$(near call $CONTRACT_ID mint_mine_nft --accountId $NEAR_ID --deposit 0.01 --gas 60000000000000)
 echo $LAND_MINT_RESPONSE
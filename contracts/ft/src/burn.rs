@@ -0,0 +1,40 @@
+use crate::*;
+
+impl Contract {
+    // Withdraw tokens from the account, reduces total supply
+    pub(crate) fn internal_burn(&mut self, account_id: &AccountId, amount: Balance, memo: Option<String>) {
+        assert!(amount > 0, "The amount should be a positive number");
+        self.token.internal_withdraw(account_id, amount);
+        self.internal_track_burn(account_id, amount, memo);
+    }
+
+    pub(crate) fn internal_track_burn(&mut self, account_id: &AccountId, amount: Balance, memo: Option<String>) {
+        self.total_burned += amount;
+
+        EventLog::new(EventLogVariant::FtBurn(vec![FtBurnLog {
+            owner_id: account_id.to_string(),
+            amount: amount.to_string(),
+            memo,
+        }])).emit();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // Burn own tokens
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.internal_burn(&env::predecessor_account_id(), amount.0, memo);
+    }
+
+    // Burn tokens received as payments by the parent contract
+    pub fn ft_burn_from(&mut self, amount: U128, memo: Option<String>) {
+        self.assert_owner();
+        self.internal_burn(&env::predecessor_account_id(), amount.0, memo);
+    }
+
+    pub fn ft_total_burned(&self) -> U128 {
+        self.total_burned.into()
+    }
+}
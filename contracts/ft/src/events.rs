@@ -0,0 +1,150 @@
+use std::fmt;
+
+use near_sdk::env;
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub const FT_STANDARD_NAME: &str = "nep141";
+pub const FT_EVENT_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+// variant names are the NEP-141 event names
+#[allow(clippy::enum_variant_names)]
+pub enum EventLogVariant {
+    FtMint(Vec<FtMintLog>),
+    FtBurn(Vec<FtBurnLog>),
+    FtTransfer(Vec<FtTransferLog>),
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. nep141
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl EventLog {
+    pub fn new(event: EventLogVariant) -> Self {
+        Self {
+            standard: FT_STANDARD_NAME.to_string(),
+            version: FT_EVENT_VERSION.to_string(),
+            event,
+        }
+    }
+
+    pub fn emit(&self) {
+        env::log(self.to_string().as_bytes());
+    }
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &near_sdk::serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture token minting
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `amount`: the number of tokens minted, wrapped in quotes
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMintLog {
+    pub owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of the burned tokens
+/// * `amount`: the number of tokens burned, wrapped in quotes
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtBurnLog {
+    pub owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token transfer
+///
+/// Arguments
+/// * `old_owner_id`: "owner.near"
+/// * `new_owner_id`: "receiver.near"
+/// * `amount`: the number of tokens transferred, wrapped in quotes
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtTransferLog {
+    pub old_owner_id: String,
+    pub new_owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nep_format_mint() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"foundation.near","amount":"500","memo":"Stone kill"}]}"#;
+        let log = EventLog::new(EventLogVariant::FtMint(vec![FtMintLog {
+            owner_id: "foundation.near".to_owned(),
+            amount: "500".to_string(),
+            memo: Some("Stone kill".to_string()),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_burn() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"foundation.near","amount":"250"}]}"#;
+        let log = EventLog::new(EventLogVariant::FtBurn(vec![FtBurnLog {
+            owner_id: "foundation.near".to_owned(),
+            amount: "250".to_string(),
+            memo: None,
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_transfer() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"user1.near","new_owner_id":"user2.near","amount":"42","memo":"refund"}]}"#;
+        let log = EventLog::new(EventLogVariant::FtTransfer(vec![FtTransferLog {
+            old_owner_id: "user1.near".to_string(),
+            new_owner_id: "user2.near".to_string(),
+            amount: "42".to_string(),
+            memo: Some("refund".to_string()),
+        }]));
+        assert_eq!(expected, log.to_string());
+    }
+}
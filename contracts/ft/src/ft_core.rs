@@ -0,0 +1,130 @@
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+use crate::*;
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = 5_000_000_000_000;
+const GAS_FOR_FT_TRANSFER_CALL: Gas = 25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
+const NO_DEPOSIT: Balance = 0;
+
+#[ext_contract(ext_fungible_token_receiver)]
+trait FungibleTokenReceiver {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+trait FungibleTokenResolverExt {
+    fn ft_resolve_transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128) -> U128;
+}
+
+impl Contract {
+    // Same as FungibleToken::internal_transfer, but emits ft_transfer event instead of plain logs
+    pub(crate) fn internal_ft_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        assert_ne!(sender_id, receiver_id, "Sender and receiver should be different");
+        assert!(amount > 0, "The amount should be a positive number");
+        self.token.internal_withdraw(sender_id, amount);
+        self.token.internal_deposit(receiver_id, amount);
+
+        EventLog::new(EventLogVariant::FtTransfer(vec![FtTransferLog {
+            old_owner_id: sender_id.to_string(),
+            new_owner_id: receiver_id.to_string(),
+            amount: amount.to_string(),
+            memo,
+        }])).emit();
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_ft_transfer(&sender_id, receiver_id.as_ref(), amount.into(), memo);
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_ft_transfer(&sender_id, receiver_id.as_ref(), amount.into(), memo);
+
+        // Initiating receiver's call and the callback
+        ext_fungible_token_receiver::ft_on_transfer(
+            sender_id.clone(),
+            amount,
+            msg,
+            receiver_id.as_ref(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+        )
+        .then(ext_self::ft_resolve_transfer(
+            sender_id,
+            receiver_id.into(),
+            amount,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.token.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: ValidAccountId) -> U128 {
+        self.token.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    // Refund unused tokens to sender, returns used amount
+    #[private]
+    fn ft_resolve_transfer(&mut self, sender_id: ValidAccountId, receiver_id: ValidAccountId, amount: U128) -> U128 {
+        let sender_id: AccountId = sender_id.into();
+        let receiver_id: AccountId = receiver_id.into();
+        let amount: Balance = amount.into();
+
+        // Get the unused amount from the `ft_on_transfer` call result.
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(value) => {
+                if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
+                    std::cmp::min(amount, unused_amount.0)
+                } else {
+                    amount
+                }
+            }
+            PromiseResult::Failed => amount,
+        };
+
+        let receiver_balance = self.token.accounts.get(&receiver_id).unwrap_or(0);
+        let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+        if refund_amount == 0 {
+            return amount.into();
+        }
+
+        if self.token.accounts.contains_key(&sender_id) {
+            self.internal_ft_transfer(&receiver_id, &sender_id, refund_amount, Some("refund".to_string()));
+        } else {
+            // Sender's account was deleted, so we need to burn tokens.
+            self.token.internal_withdraw(&receiver_id, refund_amount);
+            self.on_tokens_burned(receiver_id, refund_amount);
+            return amount.into();
+        }
+        (amount - refund_amount).into()
+    }
+}
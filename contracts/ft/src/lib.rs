@@ -15,14 +15,27 @@ NOTES:
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
  */
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_contract_standards::fungible_token::FungibleToken;
 use near_contract_standards::fungible_token::metadata::{
     FT_METADATA_SPEC, FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
-use near_sdk::{AccountId, Balance, env, log, near_bindgen, PanicOnDefault, Promise, PromiseOrValue};
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
+use near_sdk::{AccountId, assert_one_yocto, Balance, env, log, near_bindgen, PanicOnDefault, Promise, PromiseOrValue};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::{U128, ValidAccountId};
+use near_sdk::collections::{LazyOption, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{U128, U64, ValidAccountId};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::events::*;
+use crate::mint::*;
+use crate::vesting::*;
+
+mod burn;
+mod events;
+mod ft_core;
+mod mint;
+mod vesting;
 
 near_sdk::setup_alloc!();
 
@@ -31,6 +44,17 @@ near_sdk::setup_alloc!();
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    owner_id: AccountId,
+    minters: UnorderedSet<AccountId>,
+    total_minted: Balance,
+    total_cap: Option<Balance>,
+    period_minted: Balance,
+    period_cap: Option<Balance>,
+    period_duration: u64,
+    period_started_at: u64,
+    total_burned: Balance,
+    vestings: UnorderedMap<AccountId, VestingSchedule>,
+    vesting_admin_id: AccountId,
 }
 
 const FT_IMAGE_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 512 512'%3E%3Cpath d='m92.8 325.1 2.1-19.1s-4.2-12.5 5-13.7 9.1-.8 26.1-3.7c17-2.9 12-3.3 28.2-7.1 16.2-3.7 3.7-6.6 19.1-5.4 15.4 1.2 64.8 3.3 70.2 4.2s7.5-2.1 7.5 1.2 5 8.7 23.3 10 12.9 3.3 21.2 1.2c8.3-2.1 26.6-4.2 33.6-1.2 7.1 2.9 17.4 12 31.1 12 13.7 0 24.3 5.8 28.8 14.9s2.3 4.2 8.5 8.3c6.2 4.2 17.8 1.7 23.2 10.8 5.4 9.1 6.6 11.6 0 19.5s-36.9 10.8-46.1 11.2-15.4-2.9-28.2 2.5c-12.9 5.4 19.5 5.8-19.1 5.4-38.6-.4-27.4 2.1-46.1-3.3-18.7-5.4-50.6-10.4-50.6-10.4s-81.4-19.1-85.5-20.3c-4.2-1.2-18.3-8.7-23.7-7.9-5.4.8-2.9 5.8-13.7.8-10.7-4.9-14.9-9.9-14.9-9.9z' fill='%23666'/%3E%3Cpath d='M114.9 350.4s-.6.6 5.4 2.6 5.5 1.4 8.9 2c3.4.6 4.2.3 8.7 3.8s2.6 1.4 8.4 2.5c5.7 1 19.2 15.1 47.8 17.2 28.6 2.1 23.7 1.3 34.7 2.5 11 1.2 8.2-2.9 27.4 5s16.8 5.8 27 8.7 40.4-3.8 47-3.8c6.5 0 3.3-6.2 15.9-1.7 12.7 4.6 18.8 6.2 22.9 6.2 4.1 0 7-5.4 15.5-7.9 8.6-2.5 18.8-10 24.9-11.2 6.1-1.2 9.8-7.9 9.8-7.9l7-13.4s2.5-1.1 2.3-2.5c-.2-1.4-5.4-6.7-5.4-6.7l-3-8.6-.6 1.9c3.2 6.1 3.2 8.9-2.3 15.5-.8 1-2.1 2-3.6 2.8-10.3 5.8-33.9 8-41.7 8.4-9 .4-15.1-2.9-27.8 2.5-8.6 3.7-2.8 5.1-6.3 5.5-1.6.2-.4 0-12.5-.1-38-.4-27 2.1-45.4-3.3S230 360 230 360s-80.1-19.1-84.2-20.3c-4.1-1.2-18-8.7-23.3-7.9-5.3.8-2.9 5.8-13.5.8s-14.7-10-14.7-10l2-19.1s-1-3.1-.9-6.4c-1.8 1.4-4.1 3.1-5.1 7.6-1 4.6-6.7 16.8-6.7 19.9 0 3.1 4.7 8.5 8 13.9s14.3 12.7 14.3 12.7l9-.8z' fill='%235c5c5c'/%3E%3Cpath d='M284 247.5s16.6-24.4 21.3-45.6c4.8-21.2 4.8-25.5 4.8-25.5l17.5-25.2c1.2-1.8 2.6-8.9-1.1-11.9-5-6.5-10.8-7.8-15.8-4.8-3.7 2.3-7.7 9.5-7.3 13l-.4 6.1c-9.6 10-8.8 10.4-12.7 14.2-1.5 1.4-5.9-2.5-5.8-2.4.5.6 4.2-14.5 4.2-22.4 0-7.9 13.2-23 16.9-31.9 3.7-9-8.8-19.6-18-8.2-9.2 11.4-13.9 25-13.9 25l-8.7 31.6-7-.4s-1.3-26.4-.4-31c.5-2.3 3.9-11.6 7-20.8 3-8.9 5.7-17.8 5.2-20.5-1.1-5.6-13.2-10.4-19.5-.8-6.4 9.5-12.7 46.4-13.2 48-.5 1.6-1.6 11.7-1.6 16.2s-.1 10.1-.1 10.1l-5.3-2.5c-3.6-1.7-3.2-8.5-3.9-15.6-.8-8.8-2-18.4.3-22.8 4.1-7.6 1.5-10.4-4.4-16.2-2.1-.1-12.7-3.3-16.5 3-4.8 6.9-3.2 12.1-2.9 17.9.6 10.5 3.7 20.5 3.7 28.9 0 1.2 1.7 6.2 3.3 11.2-3.6 2-8.1 4.3-11.3 5.5-6.4 2.4-7.2 5-7.2 7.9 0 2.9 3.7 16.7 4.2 25.7.4 7.2 10.4 15 17.2 19 6.1 15.2 12.4 10.3 14 21.3 2.5 16.4-2 48.6-6.2 79.9 9.2-2 17.6-2.2 24.1-.6 7.3 1.8 5.4 4.7 16.2-2.9s1-10.5 19.1-2.6l2.3 1 1.9-70.9z' fill='%23edd6c0'/%3E%3Cpath fill='%23999' d='M253 82.9c1.8-3.9 7.1-14.9 13.2-18 6.1-3 7.1 2.4 7.1 2.4-.4 3.3-3 13.1-4.3 17.4-2.6-3.9-10.3-6.4-16-1.8zM211.1 106.4s9.4 15.5 16.7 15c4-.3.1-9.2-1-10.9l-2.9-4.2c-4.7-1.3-9-1.5-12.8.1zM305.7 146.8l6.2-9.7c2.6-1.2 9.1.5 9.7 4.1.3 1.7-5 6.5-9.4 11.9-4.8 5.9-14.6 7.6-6.5-6.3zM292.9 98.6c3.4-4.2 8.5-9.5 13.1-10.8 6.5-1.8 6.6 3.7 6.6 3.7-.9 2.7-4.1 9.5-6.4 14-1.2-5.3-7.2-9.5-13.3-6.9z'/%3E%3Cpath d='M221.9 164s4.4 9.5 1.6 14.6c-2.8 5-9.5 6.8-12 8.5l1.5 3.2s27.9-4.5 36.9 13.4 9 18.1 9 18.1 7.8-18.5 13.4-27.3c5.7-8.8 8.4-11.3 8.4-11.3s-9 1.6-15.6-1c-6.6-2.5-7-3.7-13.4-3.3-5.1.3-16.8 7.3-25 1.2 2.1-10-2.5-14.7-4.8-16.1z' fill='%23ddc8b5'/%3E%3Cpath fill='%23999' d='M205.8 167.3s22.3-10.3 24.5-10.6 3.2 1.3.7 4.2-22.7 13.3-22.7 13.3-1.7-5.8-2.5-6.9z'/%3E%3C/svg%3E%0A";
@@ -69,7 +93,20 @@ impl Contract {
         let mut this = Self {
             token: FungibleToken::new(b"a".to_vec()),
             metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            owner_id: owner_id.clone().into(),
+            minters: UnorderedSet::new(b"n".to_vec()),
+            total_minted: 0,
+            total_cap: None,
+            period_minted: 0,
+            period_cap: None,
+            period_duration: EMISSION_PERIOD,
+            period_started_at: env::block_timestamp(),
+            total_burned: 0,
+            vestings: UnorderedMap::new(b"v".to_vec()),
+            // deployer manages vesting, owner is usually the game contract
+            vesting_admin_id: env::predecessor_account_id(),
         };
+        this.minters.insert(owner_id.as_ref());
         this.token.internal_register_account(owner_id.as_ref());
         this.token.internal_deposit(owner_id.as_ref(), total_supply.into());
         this
@@ -77,51 +114,17 @@ impl Contract {
 
     fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
         log!("Closed @{} with {}", account_id, balance);
+        // balance of force closed account is removed from total supply
+        if balance > 0 {
+            self.internal_track_burn(&account_id, balance, Some("Account closed".to_string()));
+        }
     }
 
     fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
-        log!("Account @{} burned {}", account_id, amount);
-    }
-
-    #[payable]
-    pub fn ft_mint(
-        &mut self,
-        receiver_id: AccountId,
-        amount: U128,
-    ) {
-        //get initial storage usage
-        assert_eq!(amount.0, 0, "Cannot mint tokens, just 0 for approve");
-
-        let initial_storage_usage = env::storage_usage();
-
-        let mut amount_for_account = self.token.accounts.get(&receiver_id).unwrap_or(0);
-        amount_for_account += amount.0;
-
-        self.token.accounts.insert(&receiver_id, &amount_for_account);
-        self.token.total_supply = self
-            .token
-            .total_supply
-            .checked_add(amount.0)
-            .unwrap_or_else(|| env::panic(b"Total supply overflow"));
-
-        //refund any excess storage
-        let storage_used = env::storage_usage() - initial_storage_usage;
-        let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-        let attached_deposit = env::attached_deposit();
-
-        assert!(
-            required_cost <= attached_deposit,
-            "Must attach {} yoctoNEAR to cover storage", required_cost
-        );
-
-        let refund = attached_deposit - required_cost;
-        if refund > 1 {
-            Promise::new(env::predecessor_account_id()).transfer(refund);
-        }
+        self.internal_track_burn(&account_id, amount, None);
     }
 }
 
-near_contract_standards::impl_fungible_token_core!(Contract, token, on_tokens_burned);
 near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
 
 #[near_bindgen]
@@ -133,9 +136,9 @@ impl FungibleTokenMetadataProvider for Contract {
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
-    use near_sdk::{Balance, testing_env};
+    use near_sdk::{Balance, PromiseResult, testing_env};
     use near_sdk::MockedBlockchain;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
 
     use super::*;
 
@@ -188,6 +191,13 @@ mod tests {
             .build());
         let transfer_amount = TOTAL_SUPPLY / 3;
         contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{{"old_owner_id":"charlie","new_owner_id":"bob","amount":"{}"}}]}}"#,
+                transfer_amount,
+            )]
+        );
 
         testing_env!(context
             .storage_usage(env::storage_usage())
@@ -198,4 +208,161 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    #[test]
+    fn test_resolve_transfer_refund() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.token.internal_register_account(accounts(1).as_ref());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+
+        // receiver used 60 of 100 tokens
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"40\"".to_vec())]
+        );
+        assert_eq!(contract.ft_resolve_transfer(accounts(2), accounts(1), 100.into()).0, 60);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 60);
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"charlie","amount":"40","memo":"refund"}]}"#]
+        );
+    }
+
+    #[test]
+    fn test_resolve_transfer_burn_for_deleted_sender() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.token.internal_register_account(accounts(1).as_ref());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+        contract.token.accounts.remove(accounts(3).as_ref());
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Failed]
+        );
+        assert_eq!(contract.ft_resolve_transfer(accounts(3), accounts(1), 100.into()).0, 100);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 100);
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"100"}]}"#]
+        );
+    }
+
+    #[test]
+    fn test_mint_by_minter() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.add_minter(accounts(3));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.ft_mint(accounts(1), 500.into(), Some("Stone kill".to_string()));
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 500);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 500);
+        assert_eq!(contract.ft_emission().total_minted.0, 500);
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"500","memo":"Stone kill"}]}"#]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only minters can mint tokens")]
+    fn test_mint_not_minter() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.add_minter(accounts(3));
+        contract.remove_minter(accounts(3));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(2), 500.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Period emission cap exceeded")]
+    fn test_mint_period_cap() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.set_emission_caps(Some(2_000.into()), Some(600.into()), None);
+        contract.ft_mint(accounts(2), 600.into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(EMISSION_PERIOD)
+            .build());
+        contract.ft_mint(accounts(2), 600.into(), None);
+        assert_eq!(contract.ft_emission().period_minted.0, 600);
+        contract.ft_mint(accounts(2), 1.into(), None);
+    }
+
+    #[test]
+    fn test_burn() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.ft_burn(1_000.into(), Some("Sink".to_string()));
+
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_total_burned().0, 1_000);
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"charlie","amount":"1000","memo":"Sink"}]}"#]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can call this method")]
+    fn test_burn_from_not_parent() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_burn_from(1_000.into(), None);
+    }
+
+    #[test]
+    fn test_burn_from_parent() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.ft_burn_from(1_000.into(), Some("Stone fusion".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_total_burned().0, 1_000);
+    }
+
+    #[test]
+    fn test_force_unregister_burns_balance() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.token.internal_register_account(accounts(1).as_ref());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        assert!(contract.storage_unregister(Some(true)));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_total_burned().0, 100);
+        assert_eq!(
+            get_logs()[1],
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"100","memo":"Account closed"}]}"#
+        );
+    }
 }
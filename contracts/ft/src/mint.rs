@@ -0,0 +1,132 @@
+use crate::*;
+
+// Length of an emission period in nanoseconds (1 day)
+pub const EMISSION_PERIOD: u64 = 86_400_000_000_000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmissionInfo {
+    pub total_minted: U128,
+    pub total_cap: Option<U128>,
+    pub period_minted: U128,
+    pub period_cap: Option<U128>,
+    pub period_duration: U64,
+    pub period_started_at: U64,
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Only owner can call this method");
+    }
+
+    pub(crate) fn assert_minter(&self) {
+        assert!(self.minters.contains(&env::predecessor_account_id()), "Only minters can mint tokens");
+    }
+
+    // Count amount against the total and current period caps
+    pub(crate) fn internal_track_emission(&mut self, amount: Balance) {
+        let now = env::block_timestamp();
+        if now >= self.period_started_at + self.period_duration {
+            self.period_started_at = now;
+            self.period_minted = 0;
+        }
+
+        let total_minted = self.total_minted.checked_add(amount).expect("Total emission overflow");
+        if let Some(total_cap) = self.total_cap {
+            assert!(total_minted <= total_cap, "Total emission cap exceeded");
+        }
+        let period_minted = self.period_minted + amount;
+        if let Some(period_cap) = self.period_cap {
+            assert!(period_minted <= period_cap, "Period emission cap exceeded");
+        }
+
+        self.total_minted = total_minted;
+        self.period_minted = period_minted;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // Mint new tokens, registers receiver storage if needed
+    #[payable]
+    pub fn ft_mint(
+        &mut self,
+        receiver_id: ValidAccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        self.assert_minter();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        let receiver_id: AccountId = receiver_id.into();
+
+        //get initial storage usage
+        let initial_storage_usage = env::storage_usage();
+
+        if !self.token.accounts.contains_key(&receiver_id) {
+            self.token.internal_register_account(&receiver_id);
+        }
+        self.internal_track_emission(amount.0);
+        self.token.internal_deposit(&receiver_id, amount.0);
+
+        EventLog::new(EventLogVariant::FtMint(vec![FtMintLog {
+            owner_id: receiver_id,
+            amount: amount.0.to_string(),
+            memo,
+        }])).emit();
+
+        //refund any excess storage
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+        let attached_deposit = env::attached_deposit();
+
+        assert!(
+            required_cost <= attached_deposit,
+            "Must attach {} yoctoNEAR to cover storage", required_cost
+        );
+
+        let refund = attached_deposit - required_cost;
+        if refund > 1 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    pub fn add_minter(&mut self, account_id: ValidAccountId) {
+        self.assert_owner();
+        self.minters.insert(account_id.as_ref());
+    }
+
+    pub fn remove_minter(&mut self, account_id: ValidAccountId) {
+        self.assert_owner();
+        self.minters.remove(account_id.as_ref());
+    }
+
+    // Set emission caps, None removes the cap
+    pub fn set_emission_caps(&mut self, total_cap: Option<U128>, period_cap: Option<U128>, period_duration: Option<U64>) {
+        self.assert_owner();
+        if let Some(total_cap) = total_cap {
+            assert!(total_cap.0 >= self.total_minted, "Total cap is lower than already minted");
+        }
+        self.total_cap = total_cap.map(|cap| cap.0);
+        self.period_cap = period_cap.map(|cap| cap.0);
+        if let Some(period_duration) = period_duration {
+            assert!(period_duration.0 > 0, "Period duration should be positive");
+            self.period_duration = period_duration.0;
+        }
+    }
+
+    pub fn ft_minters(&self) -> Vec<AccountId> {
+        self.minters.to_vec()
+    }
+
+    pub fn ft_emission(&self) -> EmissionInfo {
+        let period_ended = env::block_timestamp() >= self.period_started_at + self.period_duration;
+        EmissionInfo {
+            total_minted: self.total_minted.into(),
+            total_cap: self.total_cap.map(U128),
+            period_minted: if period_ended { 0.into() } else { self.period_minted.into() },
+            period_cap: self.period_cap.map(U128),
+            period_duration: self.period_duration.into(),
+            period_started_at: self.period_started_at.into(),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use crate::*;
+
+// Vesting grant, tokens are held in escrow on the token contract account
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VestingSchedule {
+    pub total: Balance,
+    pub claimed: Balance,
+    pub start: u64,
+    pub cliff: u64,
+    pub end: u64,
+    pub revoked: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingView {
+    pub beneficiary_id: AccountId,
+    pub total: U128,
+    pub vested: U128,
+    pub claimed: U128,
+    pub locked: U128,
+    pub start: U64,
+    pub cliff: U64,
+    pub end: U64,
+    pub revoked: bool,
+}
+
+impl VestingSchedule {
+    // All tokens are vested and claimed
+    pub fn is_finished(&self, timestamp: u64) -> bool {
+        self.vested_amount(timestamp) == self.total && self.claimed == self.total
+    }
+
+    // Nothing vests before the cliff, then amount grows linearly from start to end
+    pub fn vested_amount(&self, timestamp: u64) -> Balance {
+        if self.revoked || timestamp >= self.end {
+            return self.total;
+        }
+        if timestamp < self.cliff {
+            return 0;
+        }
+        // split to avoid overflow of total * elapsed
+        let elapsed = (timestamp - self.start) as u128;
+        let span = (self.end - self.start) as u128;
+        self.total / span * elapsed + self.total % span * elapsed / span
+    }
+
+    pub fn to_view(&self, beneficiary_id: AccountId, timestamp: u64) -> VestingView {
+        let vested = self.vested_amount(timestamp);
+        VestingView {
+            beneficiary_id,
+            total: self.total.into(),
+            vested: vested.into(),
+            claimed: self.claimed.into(),
+            locked: (self.total - vested).into(),
+            start: self.start.into(),
+            cliff: self.cliff.into(),
+            end: self.end.into(),
+            revoked: self.revoked,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_vesting_admin(&self) {
+        assert_eq!(env::predecessor_account_id(), self.vesting_admin_id, "Only vesting admin can call this method");
+    }
+
+    pub(crate) fn vesting_escrow_id(&self) -> AccountId {
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            env::panic(b"Escrow account is not registered");
+        }
+        escrow_id
+    }
+
+    // Finished schedules are removed, so beneficiary can get a new grant
+    fn save_vesting(&mut self, beneficiary_id: &AccountId, schedule: &VestingSchedule) {
+        if schedule.is_finished(env::block_timestamp()) {
+            self.vestings.remove(beneficiary_id);
+        } else {
+            self.vestings.insert(beneficiary_id, schedule);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // Hand over vesting admin role, callable by owner or current admin
+    pub fn set_vesting_admin(&mut self, account_id: ValidAccountId) {
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == self.owner_id || predecessor_id == self.vesting_admin_id,
+            "Only owner or vesting admin can call this method"
+        );
+        self.vesting_admin_id = account_id.into();
+    }
+
+    pub fn get_vesting_admin(&self) -> AccountId {
+        self.vesting_admin_id.clone()
+    }
+
+    // Lock owner tokens for beneficiary, durations are in nanoseconds from start
+    pub fn create_vesting(
+        &mut self,
+        beneficiary_id: ValidAccountId,
+        amount: U128,
+        start: U64,
+        cliff_duration: U64,
+        duration: U64,
+    ) {
+        self.assert_vesting_admin();
+        let beneficiary_id: AccountId = beneficiary_id.into();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        assert!(duration.0 > 0, "Vesting duration should be positive");
+        assert!(cliff_duration.0 <= duration.0, "Cliff is longer than vesting duration");
+        assert!(self.vestings.get(&beneficiary_id).is_none(), "Beneficiary already has vesting");
+
+        if !self.token.accounts.contains_key(&env::current_account_id()) {
+            self.token.internal_register_account(&env::current_account_id());
+        }
+        self.internal_ft_transfer(&self.owner_id.clone(), &env::current_account_id(), amount.0, Some("Vesting".to_string()));
+
+        self.vestings.insert(&beneficiary_id, &VestingSchedule {
+            total: amount.0,
+            claimed: 0,
+            start: start.0,
+            cliff: start.0 + cliff_duration.0,
+            end: start.0 + duration.0,
+            revoked: false,
+        });
+    }
+
+    // Stop vesting, unvested tokens are returned to owner. Returns revoked amount
+    pub fn revoke_vesting(&mut self, beneficiary_id: ValidAccountId) -> U128 {
+        self.assert_vesting_admin();
+        let mut schedule = self.vestings.get(beneficiary_id.as_ref()).expect("Vesting not found");
+        assert!(!schedule.revoked, "Vesting is already revoked");
+
+        let vested = schedule.vested_amount(env::block_timestamp());
+        let unvested = schedule.total - vested;
+        schedule.total = vested;
+        schedule.revoked = true;
+        self.save_vesting(beneficiary_id.as_ref(), &schedule);
+
+        if unvested > 0 {
+            let escrow_id = self.vesting_escrow_id();
+            self.internal_ft_transfer(&escrow_id, &self.owner_id.clone(), unvested, Some("Vesting revoked".to_string()));
+        }
+        unvested.into()
+    }
+
+    // Transfer vested tokens to beneficiary. Returns claimed amount
+    #[payable]
+    pub fn claim_vested(&mut self) -> U128 {
+        assert_one_yocto();
+        let beneficiary_id = env::predecessor_account_id();
+        let mut schedule = self.vestings.get(&beneficiary_id).expect("Vesting not found");
+
+        let amount = schedule.vested_amount(env::block_timestamp()) - schedule.claimed;
+        assert!(amount > 0, "Nothing to claim");
+        schedule.claimed += amount;
+        self.save_vesting(&beneficiary_id, &schedule);
+
+        let escrow_id = self.vesting_escrow_id();
+        self.internal_ft_transfer(&escrow_id, &beneficiary_id, amount, Some("Vesting claim".to_string()));
+        amount.into()
+    }
+
+    pub fn get_vesting(&self, beneficiary_id: ValidAccountId) -> Option<VestingView> {
+        self.vestings.get(beneficiary_id.as_ref())
+            .map(|schedule| schedule.to_view(beneficiary_id.into(), env::block_timestamp()))
+    }
+
+    pub fn get_vestings(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<VestingView> {
+        self.vestings.iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(beneficiary_id, schedule)| schedule.to_view(beneficiary_id, env::block_timestamp()))
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, testing_env};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn set_context(predecessor: usize, timestamp: u64) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(predecessor))
+            .block_timestamp(timestamp)
+            .attached_deposit(1);
+        testing_env!(builder.build());
+    }
+
+    // Owner accounts(1), 1000 tokens vesting for accounts(2) from 100 to 1100 with cliff at 300
+    fn setup_vesting() -> Contract {
+        set_context(1, 0);
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.token.internal_register_account(accounts(2).as_ref());
+        contract.create_vesting(accounts(2), 1_000.into(), 100.into(), 200.into(), 1_000.into());
+        contract
+    }
+
+    #[test]
+    fn vesting_releases_after_cliff() {
+        let mut contract = setup_vesting();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000);
+
+        set_context(2, 299);
+        assert_eq!(contract.get_vesting(accounts(2)).unwrap().vested.0, 0);
+
+        set_context(2, 600);
+        assert_eq!(contract.claim_vested().0, 500);
+        let vesting = contract.get_vesting(accounts(2)).unwrap();
+        assert_eq!((vesting.claimed.0, vesting.locked.0), (500, 500));
+
+        set_context(2, 2_000);
+        assert_eq!(contract.claim_vested().0, 500);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1_000);
+
+        // finished schedule is removed, new grant can be created
+        assert!(contract.get_vesting(accounts(2)).is_none());
+        set_context(1, 2_000);
+        contract.create_vesting(accounts(2), 100.into(), 2_000.into(), 0.into(), 100.into());
+        assert_eq!(contract.get_vesting(accounts(2)).unwrap().total.0, 100);
+    }
+
+    #[test]
+    fn revoke_returns_unvested() {
+        let mut contract = setup_vesting();
+
+        set_context(1, 350);
+        assert_eq!(contract.revoke_vesting(accounts(2)).0, 750);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 250);
+
+        set_context(2, 2_000);
+        assert_eq!(contract.claim_vested().0, 250);
+        assert!(contract.get_vesting(accounts(2)).is_none());
+    }
+
+    #[test]
+    fn vesting_admin_separate_from_owner() {
+        // token owned by accounts(3), deployed by accounts(1)
+        set_context(1, 0);
+        let mut contract = Contract::new_default_meta(accounts(3), TOTAL_SUPPLY.into());
+        contract.token.internal_register_account(accounts(2).as_ref());
+        assert_eq!(contract.get_vesting_admin(), accounts(1).to_string());
+
+        contract.create_vesting(accounts(2), 1_000.into(), 0.into(), 0.into(), 1_000.into());
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, TOTAL_SUPPLY - 1_000);
+
+        contract.set_vesting_admin(accounts(4));
+        assert_eq!(contract.get_vesting_admin(), accounts(4).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only vesting admin can call this method")]
+    fn owner_is_not_vesting_admin() {
+        set_context(1, 0);
+        let mut contract = Contract::new_default_meta(accounts(3), TOTAL_SUPPLY.into());
+
+        set_context(3, 0);
+        contract.revoke_vesting(accounts(2));
+    }
+}
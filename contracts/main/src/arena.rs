@@ -0,0 +1,227 @@
+use crate::*;
+
+pub const ELO_DEFAULT_RATING: u32 = 1200;
+pub const ELO_K_FACTOR: f64 = 32.0;
+pub const ARENA_CHALLENGES_DEFAULT_LIMIT: u64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ArenaChallenge {
+    pub challenger_id: AccountId,
+    pub monster_id: TokenId,
+    pub opponent_id: AccountId,
+    pub opponent_monster_id: TokenId,
+    // MNL put up by each side
+    pub stake: U128,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ArenaResult {
+    pub winner_id: AccountId,
+    pub winner_monster_id: TokenId,
+    pub prize: U128,
+    pub fee: U128,
+}
+
+// New ratings after the game, `score` is 1.0 when first player wins
+pub fn elo_ratings(rating: u32, opponent_rating: u32, score: f64) -> (u32, u32) {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent_rating as f64 - rating as f64) / 400.0));
+    let change = (ELO_K_FACTOR * (score - expected)).round() as i64;
+    let rating = (rating as i64 + change).max(0) as u32;
+    let opponent_rating = (opponent_rating as i64 - change).max(0) as u32;
+    (rating, opponent_rating)
+}
+
+impl Contract {
+    pub(crate) fn monster_rating(&self, monster_id: &TokenId) -> u32 {
+        self.monster_ratings.get(monster_id).unwrap_or(ELO_DEFAULT_RATING)
+    }
+
+    // Resolve PvP fight and update ratings, returns true when the first monster wins
+    pub(crate) fn pvp_fight(&mut self, monster_id: &TokenId, opponent_monster_id: &TokenId, random: &mut RandomStream) -> bool {
+        let power = |monster_id: &TokenId| self.monsters.get(monster_id).map_or(0, |monster| monster.battle_power());
+        let monster_roll = Monster::battle_roll(power(monster_id), random);
+        let opponent_roll = Monster::battle_roll(power(opponent_monster_id), random);
+        // ties go to random side
+        let win = monster_roll > opponent_roll || (monster_roll == opponent_roll && random.range(2) == 0);
+
+        let (rating, opponent_rating) = elo_ratings(
+            self.monster_rating(monster_id),
+            self.monster_rating(opponent_monster_id),
+            if win { 1.0 } else { 0.0 },
+        );
+        self.monster_ratings.insert(monster_id, &rating);
+        self.monster_ratings.insert(opponent_monster_id, &opponent_rating);
+
+        win
+    }
+
+    // Create challenge, `stake` is MNL received with ft_transfer_call
+    pub(crate) fn arena_challenge(
+        &mut self,
+        challenger_id: &AccountId,
+        monster_id: TokenId,
+        opponent_monster_id: TokenId,
+        stake: u128,
+    ) -> u64 {
+        self.assert_monster_owner(&monster_id, challenger_id);
+        let opponent_id = self.monster_owner(&opponent_monster_id);
+        if &opponent_id == challenger_id {
+            panic!("You can't challenge yourself");
+        }
+        if stake == 0 {
+            panic!("Stake should be positive");
+        }
+
+        self.arena_challenge_count += 1;
+        self.arena_challenges.insert(&self.arena_challenge_count, &ArenaChallenge {
+            challenger_id: challenger_id.to_string(),
+            monster_id,
+            opponent_id,
+            opponent_monster_id,
+            stake: U128(stake),
+            created_at: env::block_timestamp(),
+        });
+        self.arena_challenge_count
+    }
+
+    // Accept challenge paying the same stake and fight, returns result and unused MNL amount
+    pub(crate) fn arena_accept(&mut self, opponent_id: &AccountId, challenge_id: u64, amount: u128) -> (ArenaResult, u128) {
+        let challenge = self.arena_challenges.get(&challenge_id).expect("Challenge not found");
+        if &challenge.opponent_id != opponent_id {
+            panic!("Challenge is not for you");
+        }
+        if amount < challenge.stake.0 {
+            panic!("Stake {} MNL yocto to accept", challenge.stake.0);
+        }
+        self.assert_monster_owner(&challenge.opponent_monster_id, opponent_id);
+        self.arena_challenges.remove(&challenge_id);
+
+        // challenger forfeits if the monster is gone, ratings are updated only after a fight
+        let challenger_wins = self.monster_owned_by(&challenge.monster_id, &challenge.challenger_id) && {
+            let mut random = self.random_stream(opponent_id, &challenge.opponent_monster_id);
+            self.pvp_fight(&challenge.monster_id, &challenge.opponent_monster_id, &mut random)
+        };
+
+        let pot = challenge.stake.0 * 2;
+        let fee = pot * self.arena_fee_bps as u128 / 10_000;
+        let (winner_id, winner_monster_id) = if challenger_wins {
+            (challenge.challenger_id.to_string(), challenge.monster_id.to_string())
+        } else {
+            (challenge.opponent_id.to_string(), challenge.opponent_monster_id.to_string())
+        };
+        self.pay_mnl_transfer(&winner_id, pot - fee);
+
+        let result = ArenaResult {
+            winner_id,
+            winner_monster_id,
+            prize: U128(pot - fee),
+            fee: U128(fee),
+        };
+        EventLog::new(EventLogVariant::ArenaBattle(vec![ArenaBattleLog {
+            challenge_id,
+            challenge,
+            result: result.clone(),
+        }])).emit();
+
+        (result, amount - pot / 2)
+    }
+
+    // Cancel challenge by any side, stake is returned to challenger
+    pub(crate) fn arena_cancel(&mut self, account_id: &AccountId, challenge_id: u64) {
+        let challenge = self.arena_challenges.get(&challenge_id).expect("Challenge not found");
+        if &challenge.challenger_id != account_id && &challenge.opponent_id != account_id {
+            panic!("Challenge is not yours");
+        }
+        self.arena_challenges.remove(&challenge_id);
+        self.pay_mnl_transfer(&challenge.challenger_id, challenge.stake.0);
+    }
+
+    pub(crate) fn arena_challenges_page(&self, account_id: &AccountId, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, ArenaChallenge> {
+        self.arena_challenges.keys_as_vector().iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(ARENA_CHALLENGES_DEFAULT_LIMIT) as usize)
+            .flat_map(|challenge_id| self.arena_challenges.get(&challenge_id).map(|challenge| (challenge_id, challenge)))
+            .filter(|(_, challenge)| &challenge.challenger_id == account_id || &challenge.opponent_id == account_id)
+            .collect()
+    }
+
+    pub(crate) fn monster_owner(&self, monster_id: &TokenId) -> AccountId {
+        self.monsters.get(monster_id).expect("Monster not found").owner_id
+    }
+
+    pub(crate) fn monster_owned_by(&self, monster_id: &TokenId, account_id: &AccountId) -> bool {
+        matches!(self.monsters.get(monster_id), Some(monster) if &monster.owner_id == account_id)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn elo_rating_changes() {
+        assert_eq!(elo_ratings(1200, 1200, 1.0), (1216, 1184));
+        assert_eq!(elo_ratings(1200, 1200, 0.0), (1184, 1216));
+        // upset win against stronger opponent gives more points
+        assert_eq!(elo_ratings(1000, 1400, 1.0), (1029, 1371));
+    }
+
+    #[test]
+    fn challenge_and_accept() {
+        let mut contract = setup_contract();
+        let monster = mint_test_monster(&mut contract, 2);
+        let opponent_monster = mint_test_monster(&mut contract, 3);
+
+        set_context(2, 0);
+        let challenge_id = contract.arena_challenge(&accounts(2).into(), monster.to_string(), opponent_monster.to_string(), 1000);
+        assert_eq!(contract.get_arena_challenges(accounts(3).into(), None, None).len(), 1);
+
+        set_context(3, 0);
+        let (result, unused) = contract.arena_accept(&accounts(3).into(), challenge_id, 1500);
+        assert_eq!(unused, 500);
+        assert_eq!(result.prize, U128(1900));
+        assert_eq!(result.fee, U128(100));
+        assert!(get_logs().iter().any(|log| log.contains("\"event\":\"arena_battle\"")));
+        assert!(contract.get_arena_challenges(accounts(2).into(), None, None).is_empty());
+
+        let winner_rating = contract.get_monster_rating(result.winner_monster_id.to_string());
+        assert_eq!(winner_rating, 1216);
+        assert_eq!(contract.get_monster_rating(monster) + contract.get_monster_rating(opponent_monster), 2400);
+    }
+
+    #[test]
+    #[should_panic(expected = "Challenge is not for you")]
+    fn accept_foreign_challenge() {
+        let mut contract = setup_contract();
+        let monster = mint_test_monster(&mut contract, 2);
+        let opponent_monster = mint_test_monster(&mut contract, 3);
+
+        let challenge_id = contract.arena_challenge(&accounts(2).into(), monster, opponent_monster, 1000);
+        contract.arena_accept(&accounts(4).into(), challenge_id, 1000);
+    }
+
+    #[test]
+    fn gone_monster_forfeits_without_rating_change() {
+        let mut contract = setup_contract();
+        let monster = mint_test_monster(&mut contract, 2);
+        let opponent_monster = mint_test_monster(&mut contract, 3);
+
+        set_context(2, 0);
+        let challenge_id = contract.arena_challenge(&accounts(2).into(), monster.to_string(), opponent_monster.to_string(), 1000);
+        let challenger_monster = contract.get_monster(monster.to_string());
+        contract.monster_unlink_from_user(&challenger_monster);
+
+        set_context(3, 0);
+        let (result, _) = contract.arena_accept(&accounts(3).into(), challenge_id, 1000);
+        assert_eq!(result.winner_monster_id, opponent_monster);
+        assert_eq!(contract.get_monster_rating(opponent_monster), ELO_DEFAULT_RATING);
+        assert_eq!(contract.get_monster_rating(monster), ELO_DEFAULT_RATING);
+    }
+}
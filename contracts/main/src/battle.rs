@@ -0,0 +1,209 @@
+use crate::*;
+
+// Monster can battle once per day
+pub const MONSTER_BATTLE_COOLDOWN: u64 = 1_000_000_000 * 60 * 60 * 24;
+// Number of last battles kept for each monster
+pub const MONSTER_BATTLE_HISTORY_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleStage {
+    pub enemy_power: u32,
+    // MNL reward for win
+    pub reward: U128,
+    pub xp: u32,
+}
+
+impl BattleStage {
+    pub fn default_for(stage: u32) -> Self {
+        BattleStage {
+            enemy_power: 10 * stage,
+            reward: U128(stage as u128 * 1_000_000_000_000_000_000_000_000), // `stage` MNL
+            xp: 10 * stage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleResult {
+    pub stage: u32,
+    pub monster_roll: u32,
+    pub enemy_roll: u32,
+    pub win: bool,
+    pub reward: U128,
+    pub xp: u32,
+    pub timestamp: u64,
+}
+
+impl Monster {
+    // Hardness hits, density and durability absorb damage, each level adds 2
+    pub fn battle_power(&self) -> u32 {
+        (self.hardness as u32 + self.bonus_hardness) * 3
+            + (self.density as u32 + self.bonus_density) * 2
+            + (self.durability as u32 + self.bonus_durability) * 2
+            + (self.level - 1) * 2
+    }
+
+    // Power plus random bonus up to half of it
+    pub fn battle_roll(power: u32, random: &mut RandomStream) -> u32 {
+        power + random.range(power / 2 + 1)
+    }
+}
+
+impl Contract {
+    pub(crate) fn update_battle_stage(&mut self, stage: u32, battle_stage: Option<BattleStage>) {
+        match battle_stage {
+            Some(battle_stage) => {
+                if battle_stage.enemy_power == 0 {
+                    panic!("Enemy power should be positive");
+                }
+                self.battle_stages.insert(&stage, &battle_stage);
+            }
+            None => {
+                self.battle_stages.remove(&stage);
+            }
+        }
+    }
+
+    pub(crate) fn monster_battle(&mut self, owner_id: &AccountId, monster_id: TokenId, stage: u32) -> BattleResult {
+        let mut monster = self.assert_monster_owner(&monster_id, owner_id);
+        let battle_stage = self.battle_stages.get(&stage).expect("Battle stage not found");
+
+        let timestamp = env::block_timestamp();
+        if let Some(last_battle_at) = monster.last_battle_at {
+            if timestamp < last_battle_at + MONSTER_BATTLE_COOLDOWN {
+                panic!("Monster is resting, next battle at {}", last_battle_at + MONSTER_BATTLE_COOLDOWN);
+            }
+        }
+
+        let mut random = self.random_stream(owner_id, &monster_id);
+        let monster_roll = Monster::battle_roll(monster.battle_power(), &mut random);
+        let enemy_roll = Monster::battle_roll(battle_stage.enemy_power, &mut random);
+        let win = monster_roll > enemy_roll;
+
+        let result = BattleResult {
+            stage,
+            monster_roll,
+            enemy_roll,
+            win,
+            reward: if win { battle_stage.reward } else { U128(0) },
+            xp: if win { battle_stage.xp } else { 0 },
+            timestamp,
+        };
+
+        monster.xp += result.xp;
+        monster.last_battle_at = Some(timestamp);
+        if self.monster_level_up(&mut monster) {
+            self.update_monster_metadata(&monster);
+        }
+        self.monsters.insert(&monster_id, &monster);
+
+        let mut history = self.monster_battles.get(&monster_id).unwrap_or(vec![]);
+        history.push(result.clone());
+        if history.len() > MONSTER_BATTLE_HISTORY_SIZE {
+            history.remove(0);
+        }
+        self.monster_battles.insert(&monster_id, &history);
+
+        if result.reward.0 > 0 {
+            self.pay_mnl_reward(owner_id, result.reward.0, "Monster battle");
+        }
+
+        EventLog::new(EventLogVariant::MonsterBattle(vec![MonsterBattleLog {
+            owner_id: owner_id.to_string(),
+            monster_id,
+            result: result.clone(),
+        }])).emit();
+
+        result
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+
+    use super::*;
+
+    const DAY: u64 = MONSTER_BATTLE_COOLDOWN;
+
+    fn set_context(timestamp: u64, deposit: Balance) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(timestamp)
+            .attached_deposit(deposit);
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+    }
+
+    // Contract with monster of accounts(1), crafted from one-image collection
+    fn setup_monster() -> (Contract, TokenId) {
+        set_context(10 * DAY, 0);
+        let mut contract = Contract::default();
+        set_context(10 * DAY, contract.to_yocto("0.01"));
+        contract.add_collection("Collection 1".to_string(), "image".to_string(), vec!["1-1".to_string()]);
+        let mine_id = contract.mint_mine_nft().token_id;
+        let stone = contract.mint_free_stone_nft(mine_id).pop().unwrap();
+        let monster = contract.mint_collection(vec![stone.token_id], 1);
+        contract.mnl_reserve = MnlReserve { total: Balance::MAX, period: Balance::MAX, synced: true, ..Default::default() };
+        set_context(10 * DAY, 1);
+        (contract, monster.token_id)
+    }
+
+    #[test]
+    fn battle_result_and_history() {
+        let (mut contract, monster_id) = setup_monster();
+        contract.set_battle_stage(1, Some(BattleStage { enemy_power: 1, reward: U128(5), xp: 120 }));
+
+        let result = contract.battle_monster(monster_id.to_string(), 1);
+        // weakest monster power is 3 + 2 = 5, enemy roll is 1
+        assert!(result.win);
+        assert_eq!(result.reward, U128(5));
+        let monster = contract.get_monster(monster_id.to_string());
+        assert_eq!(monster.xp, 120);
+        assert_eq!(monster.level, 2);
+        assert_eq!(contract.get_monster_battles(monster_id).len(), 1);
+        assert!(get_logs().iter().any(|log| log.contains("\"event\":\"monster_battle\"")));
+    }
+
+    #[test]
+    fn battle_reward_minted_from_reserve() {
+        let (mut contract, monster_id) = setup_monster();
+        contract.mnl_reserve.total = 5;
+        contract.set_battle_stage(1, Some(BattleStage { enemy_power: 1, reward: U128(5), xp: 120 }));
+
+        contract.battle_monster(monster_id, 1);
+        assert_eq!(contract.get_mnl_treasury().reserve.0, 0);
+        assert_eq!(contract.mnl_reserve.minting, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough MNL reserve for reward")]
+    fn battle_reward_needs_reserve() {
+        let (mut contract, monster_id) = setup_monster();
+        contract.mnl_reserve.total = 4;
+        contract.set_battle_stage(1, Some(BattleStage { enemy_power: 1, reward: U128(5), xp: 120 }));
+        contract.battle_monster(monster_id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Monster is resting")]
+    fn battle_cooldown() {
+        let (mut contract, monster_id) = setup_monster();
+        contract.battle_monster(monster_id.to_string(), 1);
+        set_context(11 * DAY - 1, 1);
+        contract.battle_monster(monster_id, 1);
+    }
+
+    #[test]
+    fn battle_after_cooldown() {
+        let (mut contract, monster_id) = setup_monster();
+        contract.battle_monster(monster_id.to_string(), 1);
+        set_context(11 * DAY, 1);
+        contract.battle_monster(monster_id.to_string(), 1);
+        assert_eq!(contract.get_monster_battles(monster_id).len(), 2);
+    }
+}
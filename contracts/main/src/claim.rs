@@ -0,0 +1,150 @@
+use crate::*;
+
+// Claim should be revealed within this number of blocks after commit
+pub const STONE_CLAIM_REVEAL_BLOCKS: u64 = 100;
+
+#[derive(Debug, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StoneClaim {
+    pub account_id: AccountId,
+    pub mine_id: TokenId,
+    pub mine_type: MineType,
+    pub mint_count: u8,
+    pub deposit: u128,
+    pub commit_block: u64,
+}
+
+impl StoneClaim {
+    pub fn is_expired(&self, block_index: u64) -> bool {
+        block_index > self.commit_block + STONE_CLAIM_REVEAL_BLOCKS
+    }
+}
+
+impl Contract {
+    pub(crate) fn commit_claim(&mut self, mine_id: TokenId) -> StoneClaim {
+        let account_id = env::predecessor_account_id();
+        let mint_count = self.mine_claim_free_stone_count(account_id.to_string(), mine_id.clone());
+        if mint_count == 0 {
+            panic!("You can't claim stones from this Mine");
+        }
+        if self.stone_claims.contains_key(&mine_id) {
+            panic!("Stone claim already committed");
+        }
+
+        let deposit = self.stone_mint_deposit(mint_count);
+        if env::attached_deposit() < deposit {
+            panic!("Attach mint deposit!");
+        }
+
+        // Lock mine daily slot
+        let current_mine = self.mines.get(&mine_id).unwrap();
+        let claim = StoneClaim {
+            account_id,
+            mine_id: mine_id.to_string(),
+            mine_type: current_mine.mine_type.clone(),
+            mint_count,
+            deposit,
+            commit_block: env::block_index(),
+        };
+        self.update_mine_claim_timestamp(current_mine);
+        self.stone_claims.insert(&mine_id, &claim);
+
+        claim
+    }
+
+    pub(crate) fn reveal_claim(&mut self, mine_id: TokenId) -> (Vec<JsonValue>, Vec<Stone>, StoneClaim) {
+        let account_id = env::predecessor_account_id();
+        let claim = self.stone_claims.get(&mine_id).expect("No committed claim for this Mine");
+        if claim.account_id != account_id {
+            panic!("You don't own this claim");
+        }
+        if env::block_index() <= claim.commit_block {
+            panic!("Claim can be revealed only in a later block");
+        }
+
+        // Late reveal finish with fallback roll: base (Small mine) odds,
+        // so waiting for a better block seed is never profitable
+        let mine_type = if claim.is_expired(env::block_index()) {
+            MineType::Small
+        } else {
+            claim.mine_type.clone()
+        };
+        let drop_rates = self.mine_drop_rates(&mine_type);
+
+        self.stone_claims.remove(&mine_id);
+        let mut random = self.random_stream(&account_id, &mine_id);
+        let (metadata, stones) = self.generate_stones(&account_id, claim.mint_count, &mine_type, &drop_rates, &mut random);
+
+        (metadata, stones, claim)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::MockedBlockchain;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn get_context(block_index: u64, deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .block_index(block_index)
+            .block_timestamp(1_000_000_000 * 60 * 60 * 24 * 10)
+            .attached_deposit(deposit);
+        builder
+    }
+
+    fn setup_mine(contract: &mut Contract) -> TokenId {
+        testing_env!(get_context(10, contract.to_yocto("0.01")).build());
+        contract.add_new_collection("Collection 1".to_string(), "image".to_string(), vec!["1-1".to_string()]);
+        contract.mint_mine_nft().token_id
+    }
+
+    #[test]
+    fn commit_and_reveal() {
+        testing_env!(get_context(10, 0).build());
+        let mut contract = Contract::default();
+        let mine_id = setup_mine(&mut contract);
+
+        testing_env!(get_context(11, contract.to_yocto("0.01")).build());
+        let claim = contract.commit_stone_claim(mine_id.to_string());
+        assert_eq!(claim.mint_count, 1);
+        assert_eq!(contract.mine_claim_free_stone_count(accounts(1).into(), mine_id.to_string()), 0);
+
+        testing_env!(get_context(12, 0).build());
+        let stones = contract.reveal_stone_claim(mine_id.to_string());
+        assert_eq!(stones.len(), 1);
+        assert!(contract.get_stone_claim(mine_id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim can be revealed only in a later block")]
+    fn reveal_in_commit_block() {
+        testing_env!(get_context(10, 0).build());
+        let mut contract = Contract::default();
+        let mine_id = setup_mine(&mut contract);
+
+        testing_env!(get_context(11, contract.to_yocto("0.01")).build());
+        contract.commit_stone_claim(mine_id.to_string());
+        contract.reveal_stone_claim(mine_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Use commit_stone_claim")]
+    fn direct_mint_when_reveal_required() {
+        testing_env!(get_context(10, 0).build());
+        let mut contract = Contract::default();
+        let mine_id = setup_mine(&mut contract);
+
+        // accounts(1) deployed the contract and is the owner
+        testing_env!(get_context(11, 0).build());
+        contract.set_stone_claim_reveal_required(true);
+
+        testing_env!(get_context(11, contract.to_yocto("0.01")).build());
+        contract.mint_free_stone_nft(mine_id);
+    }
+}
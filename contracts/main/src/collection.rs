@@ -1,36 +1,366 @@
+use std::str::FromStr;
+
 use crate::*;
 
-#[derive(Debug, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+// Default drop weight of collections and stone images
+pub const DEFAULT_DROP_WEIGHT: u32 = 100;
+// Max total collection royalty in basis points (50%)
+pub const MAX_COLLECTION_ROYALTY: u32 = 5000;
+// Default page size for collection views
+pub const COLLECTIONS_DEFAULT_LIMIT: u64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Collection {
     pub title: String,
     pub image: String,
     pub stone_images: Vec<String>,
+    // Only active collections drop from mines
+    pub active: bool,
+    // Collection drop weight among collections available for the mine
+    pub weight: u32,
+    // Mine types which can drop this collection, None for all
+    pub mine_types: Option<Vec<MineType>>,
+    // Drop weight of each stone image
+    pub stone_image_weights: Vec<u32>,
+    // Season window (nanoseconds), collection drops only while the season is active
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+    pub description: Option<String>,
+    pub artist_id: Option<AccountId>,
+    // Perpetual royalties of minted stones in basis points
+    pub royalty: HashMap<AccountId, u32>,
+    // Rarity name => art for each stone_images index
+    pub rarity_stone_images: HashMap<String, Vec<String>>,
 }
 
-impl Contract {
-    pub(crate) fn add_new_collection(&mut self, title: String, image: String, stone_images: Vec<String>) {
-        for (_, collection) in self.collections.iter() {
-            if collection.title == title {
-                panic!("Collection already exists");
-            }
+impl Collection {
+    pub fn has_season(&self) -> bool {
+        self.starts_at.is_some() || self.ends_at.is_some()
+    }
+
+    pub fn season_started(&self, timestamp: u64) -> bool {
+        !matches!(self.starts_at, Some(starts_at) if starts_at > timestamp)
+    }
+
+    pub fn season_ended(&self, timestamp: u64) -> bool {
+        matches!(self.ends_at, Some(ends_at) if ends_at <= timestamp)
+    }
+
+    pub fn in_season(&self, timestamp: u64) -> bool {
+        self.season_started(timestamp) && !self.season_ended(timestamp)
+    }
+
+    // Rarity art for stone image index, base art if there is no rarity art
+    pub fn stone_media(&self, stone_index: usize, card_rarity: &CardRarity) -> String {
+        self.rarity_stone_images.get(&card_rarity.to_string())
+            .and_then(|images| images.get(stone_index))
+            .unwrap_or(&self.stone_images[stone_index])
+            .to_string()
+    }
+
+    pub fn drops_from(&self, mine_type: &MineType) -> bool {
+        match &self.mine_types {
+            Some(mine_types) => mine_types.contains(mine_type),
+            None => true,
         }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonCollection {
+    pub collection_id: u32,
+    #[serde(flatten)]
+    pub collection: Collection,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionDropOdds {
+    // Chance (percent) of the collection for each dropped stone
+    pub odds: f64,
+    // Chance (percent) of each stone image inside the collection
+    pub stone_image_odds: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionSeason {
+    pub collection_id: u32,
+    pub title: String,
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+    pub active: bool,
+    pub minted: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionSeasons {
+    pub current: Vec<CollectionSeason>,
+    pub upcoming: Vec<CollectionSeason>,
+    pub past: Vec<CollectionSeason>,
+}
+
+impl Contract {
+    pub(crate) fn add_new_collection(&mut self, title: String, image: String, stone_images: Vec<String>) -> u32 {
+        self.assert_unique_collection_title(&title, None);
 
-        let collection_count = self.collections.len() as u32;
-        self.collections.insert(&(collection_count + 1), &Collection {
+        self.collection_id_counter += 1;
+        let collection_id = self.collection_id_counter;
+        let stone_image_weights = vec![DEFAULT_DROP_WEIGHT; stone_images.len()];
+        self.collections.insert(&collection_id, &Collection {
             title,
             image,
             stone_images,
+            active: true,
+            weight: DEFAULT_DROP_WEIGHT,
+            mine_types: None,
+            stone_image_weights,
+            starts_at: None,
+            ends_at: None,
+            description: None,
+            artist_id: None,
+            royalty: HashMap::new(),
+            rarity_stone_images: HashMap::new(),
         });
+        collection_id
     }
 
-    pub(crate) fn get_user_collection_counts(&self, account_id: &AccountId) -> HashMap<u32, u32> {
+    fn assert_unique_collection_title(&self, title: &str, skip_id: Option<u32>) {
+        for (id, collection) in self.collections.iter() {
+            if Some(id) != skip_id && collection.title == title {
+                panic!("Collection already exists");
+            }
+        }
+    }
+
+    // Stone images can be replaced or appended, but not removed: existing stones keep their collection_index
+    pub(crate) fn update_existing_collection(
+        &mut self,
+        collection_id: u32,
+        title: Option<String>,
+        image: Option<String>,
+        stone_images: Option<Vec<String>>,
+    ) {
+        let mut collection = self.collections.get(&collection_id).expect("Collection not found");
+        let old_collection = collection.clone();
+
+        if let Some(title) = title {
+            if title.is_empty() {
+                panic!("All fields is required");
+            }
+            self.assert_unique_collection_title(&title, Some(collection_id));
+            collection.title = title;
+        }
+        if let Some(image) = image {
+            if image.is_empty() {
+                panic!("All fields is required");
+            }
+            collection.image = image;
+        }
+        if let Some(stone_images) = stone_images {
+            if stone_images.len() < collection.stone_images.len() {
+                panic!("Stone images can't be removed");
+            }
+            if stone_images.iter().any(|image| image.is_empty()) {
+                panic!("All fields is required");
+            }
+            // new stone images get default weight
+            collection.stone_image_weights.resize(stone_images.len(), DEFAULT_DROP_WEIGHT);
+            collection.stone_images = stone_images;
+        }
+        self.collections.insert(&collection_id, &collection);
+
+        EventLog::new(EventLogVariant::CollectionUpdate(vec![CollectionUpdateLog {
+            collection_id,
+            old_collection,
+            new_collection: collection,
+        }])).emit();
+    }
+
+    pub(crate) fn update_collection_drop_weights(
+        &mut self,
+        collection_id: u32,
+        weight: u32,
+        mine_types: Option<Vec<MineType>>,
+        stone_image_weights: Option<Vec<u32>>,
+    ) {
+        let mut collection = self.collections.get(&collection_id).expect("Collection not found");
+        let old_collection = collection.clone();
+
+        collection.weight = weight;
+        collection.mine_types = mine_types;
+        if let Some(stone_image_weights) = stone_image_weights {
+            if stone_image_weights.len() != collection.stone_images.len() {
+                panic!("Set weight for each stone image");
+            }
+            if stone_image_weights.iter().sum::<u32>() == 0 {
+                panic!("Stone image weights should not be all zero");
+            }
+            collection.stone_image_weights = stone_image_weights;
+        }
+        self.collections.insert(&collection_id, &collection);
+
+        EventLog::new(EventLogVariant::CollectionUpdate(vec![CollectionUpdateLog {
+            collection_id,
+            old_collection,
+            new_collection: collection,
+        }])).emit();
+    }
+
+    // Collections which can drop from mine type, with their weights
+    pub(crate) fn collection_drop_weights(&self, mine_type: &MineType) -> Vec<(u32, u32)> {
+        self.collections.iter()
+            .filter(|(collection_id, collection)| {
+                collection.active
+                    && collection.in_season(env::block_timestamp())
+                    && collection.weight > 0
+                    && collection.drops_from(mine_type)
+                    && self.supply_available(&SupplyScope::Collection(*collection_id))
+            })
+            .map(|(collection_id, collection)| (collection_id, collection.weight))
+            .collect()
+    }
+
+    pub(crate) fn collection_drop_odds(&self, mine_type: &MineType) -> HashMap<u32, CollectionDropOdds> {
+        let weights = self.collection_drop_weights(mine_type);
+        let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+
+        weights.into_iter().map(|(collection_id, weight)| {
+            let collection = self.collections.get(&collection_id).unwrap();
+            let images_total: u32 = collection.stone_image_weights.iter().sum();
+            let stone_image_odds = collection.stone_image_weights.iter()
+                .map(|image_weight| *image_weight as f64 * 100.0 / images_total as f64)
+                .collect();
+            (collection_id, CollectionDropOdds {
+                odds: weight as f64 * 100.0 / total as f64,
+                stone_image_odds,
+            })
+        }).collect()
+    }
+
+    pub(crate) fn update_collection_details(
+        &mut self,
+        collection_id: u32,
+        description: Option<String>,
+        artist_id: Option<AccountId>,
+        royalty: HashMap<AccountId, u32>,
+        rarity_stone_images: HashMap<String, Vec<String>>,
+    ) {
+        if royalty.len() > 6 {
+            panic!("Cannot add more than 6 royalty accounts");
+        }
+        if royalty.values().sum::<u32>() > MAX_COLLECTION_ROYALTY {
+            panic!("Royalty should not exceed {} basis points", MAX_COLLECTION_ROYALTY);
+        }
+
+        let mut collection = self.collections.get(&collection_id).expect("Collection not found");
+        for (card_rarity, images) in rarity_stone_images.iter() {
+            CardRarity::from_str(card_rarity).unwrap_or_else(|_| panic!("Wrong card rarity: {}", card_rarity));
+            if images.len() != collection.stone_images.len() || images.iter().any(|image| image.is_empty()) {
+                panic!("Set rarity art for each stone image");
+            }
+        }
+
+        let old_collection = collection.clone();
+        collection.description = description;
+        collection.artist_id = artist_id;
+        collection.royalty = royalty;
+        collection.rarity_stone_images = rarity_stone_images;
+        self.collections.insert(&collection_id, &collection);
+
+        EventLog::new(EventLogVariant::CollectionUpdate(vec![CollectionUpdateLog {
+            collection_id,
+            old_collection,
+            new_collection: collection,
+        }])).emit();
+    }
+
+    // Set season window, None for no limit
+    pub(crate) fn update_collection_season(&mut self, collection_id: u32, starts_at: Option<u64>, ends_at: Option<u64>) {
+        if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at) {
+            if ends_at <= starts_at {
+                panic!("Season should end after it starts");
+            }
+        }
+
+        let mut collection = self.collections.get(&collection_id).expect("Collection not found");
+        let old_collection = collection.clone();
+        collection.starts_at = starts_at;
+        collection.ends_at = ends_at;
+        self.collections.insert(&collection_id, &collection);
+
+        EventLog::new(EventLogVariant::CollectionUpdate(vec![CollectionUpdateLog {
+            collection_id,
+            old_collection,
+            new_collection: collection,
+        }])).emit();
+    }
+
+    pub(crate) fn collection_seasons(&self) -> CollectionSeasons {
+        let timestamp = env::block_timestamp();
+        let mut seasons = CollectionSeasons { current: vec![], upcoming: vec![], past: vec![] };
+
+        for (collection_id, collection) in self.collections.iter() {
+            if !collection.has_season() {
+                continue;
+            }
+            let season = CollectionSeason {
+                collection_id,
+                title: collection.title.to_string(),
+                starts_at: collection.starts_at,
+                ends_at: collection.ends_at,
+                active: collection.active,
+                minted: self.stone_supply_minted.get(&SupplyScope::Collection(collection_id)).unwrap_or(0),
+            };
+            if !collection.season_started(timestamp) {
+                seasons.upcoming.push(season);
+            } else if collection.season_ended(timestamp) {
+                seasons.past.push(season);
+            } else {
+                seasons.current.push(season);
+            }
+        }
+
+        seasons
+    }
+
+    pub(crate) fn update_collection_active(&mut self, collection_id: u32, active: bool) {
+        let mut collection = self.collections.get(&collection_id).expect("Collection not found");
+        collection.active = active;
+        self.collections.insert(&collection_id, &collection);
+
+        EventLog::new(EventLogVariant::CollectionStatus(vec![CollectionStatusLog {
+            collection_id,
+            active,
+        }])).emit();
+    }
+
+    // Collection ids page, in the order collections were added
+    pub(crate) fn collection_ids_page(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<u32> {
+        self.collections.keys_as_vector().iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(COLLECTIONS_DEFAULT_LIMIT) as usize)
+            .collect()
+    }
+
+    pub(crate) fn collections_by_ids(&self, collection_ids: Vec<u32>) -> Vec<JsonCollection> {
+        collection_ids.into_iter()
+            .flat_map(|collection_id| {
+                self.collections.get(&collection_id).map(|collection| JsonCollection { collection_id, collection })
+            })
+            .collect()
+    }
+
+    pub(crate) fn get_user_collection_counts(&self, account_id: &AccountId, collection_ids: Vec<u32>) -> HashMap<u32, u32> {
         let mut result = HashMap::new();
         let user_collections = self.user_stone_by_collection.get(account_id).unwrap_or(
             LookupMap::new(b"lm".to_vec())
         );
 
-        for (id, _) in self.collections.iter() {
+        for id in collection_ids {
             let collection_stones = user_collections.get(&id).unwrap_or(vec![]);
             result.insert(id, collection_stones.len() as u32);
         }
@@ -38,3 +368,150 @@ impl Contract {
         result
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+
+    use super::*;
+
+    fn setup() -> Contract {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+        let mut contract = Contract::default();
+        contract.add_collection("Collection 1".to_string(), "image-1".to_string(), vec!["1-1".to_string()]);
+        contract.add_collection("Collection 2".to_string(), "image-2".to_string(), vec!["2-1".to_string()]);
+        contract
+    }
+
+    #[test]
+    fn drop_only_from_active_collections() {
+        let mut contract = setup();
+        contract.set_collection_active(1, false);
+        assert!(get_logs()[0].contains("\"event\":\"collection_status\""));
+
+        let mut random = RandomStream::new(&[7u8; 32], "alice", "s-1-1", 1);
+        for _ in 0..20 {
+            assert_eq!(contract.generate_stone_random_collection(&MineType::Small, &CardRarity::Common, &mut random), 2);
+        }
+    }
+
+    #[test]
+    fn tier_restricted_weighted_odds() {
+        let mut contract = setup();
+        contract.add_collection("Premium".to_string(), "image-3".to_string(), vec!["3-1".to_string(), "3-2".to_string()]);
+        contract.set_collection_drop_weights(3, 200, Some(vec![MineType::Large]), Some(vec![3, 1]));
+
+        let small = contract.get_collection_drop_odds(MineType::Small);
+        assert!(!small.contains_key(&3));
+        assert_eq!(small.get(&1).unwrap().odds, 50.0);
+
+        let large = contract.get_collection_drop_odds(MineType::Large);
+        assert_eq!(large.get(&3).unwrap().odds, 50.0);
+        assert_eq!(large.get(&1).unwrap().odds, 25.0);
+        assert_eq!(large.get(&3).unwrap().stone_image_odds, vec![75.0, 25.0]);
+
+        let mut random = RandomStream::new(&[7u8; 32], "alice", "s-1-1", 1);
+        for _ in 0..20 {
+            assert_ne!(contract.generate_stone_random_collection(&MineType::Medium, &CardRarity::Common, &mut random), 3);
+        }
+    }
+
+    #[test]
+    fn seasons_by_time() {
+        let mut contract = setup();
+        contract.set_collection_season(1, None, Some(U64(100)));
+        contract.schedule_collection("Season 2".to_string(), "image-3".to_string(), vec!["3-1".to_string()], U64(100), None);
+
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(accounts(0)).block_timestamp(50);
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+        let seasons = contract.get_collection_seasons();
+        assert_eq!(seasons.current[0].collection_id, 1);
+        assert_eq!(seasons.upcoming[0].collection_id, 3);
+        assert!(seasons.past.is_empty());
+        assert!(!contract.get_collection_drop_odds(MineType::Small).contains_key(&3));
+
+        builder.block_timestamp(100);
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+        let seasons = contract.get_collection_seasons();
+        assert_eq!(seasons.current[0].collection_id, 3);
+        assert_eq!(seasons.past[0].collection_id, 1);
+        let odds = contract.get_collection_drop_odds(MineType::Small);
+        assert!(!odds.contains_key(&1));
+        assert!(odds.contains_key(&3));
+    }
+
+    #[test]
+    fn update_keeps_stone_images() {
+        let mut contract = setup();
+        contract.update_collection(1, None, Some("image-fixed".to_string()), Some(vec!["1-1b".to_string(), "1-2".to_string()]));
+
+        let collection = contract.get_one_collection(1);
+        assert_eq!(collection.title, "Collection 1");
+        assert_eq!(collection.image, "image-fixed");
+        assert_eq!(collection.stone_images, vec!["1-1b".to_string(), "1-2".to_string()]);
+        assert_eq!(collection.stone_image_weights, vec![DEFAULT_DROP_WEIGHT, DEFAULT_DROP_WEIGHT]);
+        assert!(get_logs()[0].starts_with("EVENT_JSON:{\"standard\":\"mineland\",\"version\":\"1.0.0\",\"event\":\"collection_update\""));
+    }
+
+    #[test]
+    fn rarity_art() {
+        let mut contract = setup();
+        let mut rarity_stone_images = HashMap::new();
+        rarity_stone_images.insert("Legendary".to_string(), vec!["1-1-gold".to_string()]);
+        let mut royalty = HashMap::new();
+        royalty.insert(accounts(2).to_string(), 500);
+        contract.set_collection_details(1, Some("First".to_string()), Some(accounts(2)), royalty, rarity_stone_images);
+
+        let collection = contract.get_one_collection(1);
+        assert_eq!(collection.stone_media(0, &CardRarity::Legendary), "1-1-gold");
+        assert_eq!(collection.stone_media(0, &CardRarity::Rare), "1-1");
+        assert_eq!(collection.artist_id, Some(accounts(2).to_string()));
+        assert_eq!(collection.royalty.get(&accounts(2).to_string()), Some(&500));
+    }
+
+    #[test]
+    #[should_panic(expected = "Set rarity art for each stone image")]
+    fn rarity_art_for_each_image() {
+        let mut contract = setup();
+        let mut rarity_stone_images = HashMap::new();
+        rarity_stone_images.insert("Rare".to_string(), vec![]);
+        contract.set_collection_details(1, None, None, HashMap::new(), rarity_stone_images);
+    }
+
+    #[test]
+    fn paginated_collections() {
+        let mut contract = setup();
+        contract.add_collection("Collection 3".to_string(), "image-3".to_string(), vec!["3-1".to_string()]);
+
+        let page: Vec<u32> = contract.get_collections(Some(1), Some(5)).iter()
+            .map(|collection| collection.collection_id)
+            .collect();
+        assert_eq!(page, vec![2, 3]);
+
+        let collections = contract.get_collections_by_ids(vec![3, 7, 1]);
+        assert_eq!(collections.len(), 2);
+        assert_eq!(collections[0].collection.title, "Collection 3");
+
+        let counts = contract.user_collection_counts(accounts(1).into(), Some(2), Some(1));
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.get(&3), Some(&0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Stone images can't be removed")]
+    fn update_removes_stone_images() {
+        let mut contract = setup();
+        contract.update_collection(1, None, None, Some(vec![]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Collection already exists")]
+    fn update_duplicate_title() {
+        let mut contract = setup();
+        contract.update_collection(1, Some("Collection 2".to_string()), None, None);
+    }
+}
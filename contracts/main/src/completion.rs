@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use crate::*;
+
+// Storage deposit for minting collection badge NFT
+pub const BADGE_MINT_DEPOSIT: &str = "0.01";
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionProgress {
+    // Number of different stone images owned
+    pub owned: u32,
+    pub total: u32,
+    pub completed: bool,
+    pub reward_claimed: bool,
+}
+
+impl Contract {
+    pub(crate) fn collection_progress(&self, account_id: &AccountId, collection_id: u32) -> CollectionProgress {
+        let collection = self.collections.get(&collection_id).expect("Collection not found");
+        let stone_ids = self.user_stone_by_collection.get(account_id)
+            .and_then(|user_collections| user_collections.get(&collection_id))
+            .unwrap_or(vec![]);
+        let owned: HashSet<u8> = self.get_stones_by_id(stone_ids).iter()
+            .map(|stone| stone.collection_index)
+            .collect();
+
+        let total = collection.stone_images.len() as u32;
+        let owned = owned.len() as u32;
+        CollectionProgress {
+            owned,
+            total,
+            completed: owned >= total,
+            reward_claimed: self.collection_reward_claimed(account_id, collection_id),
+        }
+    }
+
+    pub(crate) fn user_collections_progress(&self, account_id: &AccountId, collection_ids: Vec<u32>) -> HashMap<u32, CollectionProgress> {
+        collection_ids.into_iter()
+            .map(|collection_id| (collection_id, self.collection_progress(account_id, collection_id)))
+            .collect()
+    }
+
+    fn collection_reward_claimed(&self, account_id: &AccountId, collection_id: u32) -> bool {
+        self.collection_rewards_claimed.get(account_id)
+            .unwrap_or(vec![]).contains(&collection_id)
+    }
+
+    // Pay MNL bonus and mint non-transferable badge NFT for completed collection
+    pub(crate) fn claim_collection_reward(&mut self, account_id: &AccountId, collection_id: u32) -> u128 {
+        let progress = self.collection_progress(account_id, collection_id);
+        if !progress.completed {
+            panic!("Collection is not completed");
+        }
+        if progress.reward_claimed {
+            panic!("Collection reward already claimed");
+        }
+        if env::attached_deposit() < self.to_yocto(BADGE_MINT_DEPOSIT) {
+            panic!("Attach badge mint deposit!");
+        }
+
+        let mut collection_ids = self.collection_rewards_claimed.get(account_id).unwrap_or(vec![]);
+        collection_ids.push(collection_id);
+        self.collection_rewards_claimed.insert(account_id, &collection_ids);
+
+        // mint MNL bonus from the reserve, failed payout is kept for retry
+        let amount = self.collection_completion_reward;
+        if amount > 0 {
+            self.pay_mnl_reward(account_id, amount, "Collection completion");
+        } else {
+            self.ensure_token_storage(account_id);
+        }
+
+        // mint badge
+        let collection = self.collections.get(&collection_id).unwrap();
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"mint_badge".to_vec(),
+            json!({
+                "badge_data": {
+                    "token_id": format!("badge-{}-{}", collection_id, account_id),
+                    "receiver_id": account_id,
+                    "token_metadata": {
+                        "title": format!("{} Collector", collection.title),
+                        "media": collection.image,
+                        "copies": 1
+                    }
+                }
+            }).to_string().as_bytes().to_vec(),
+            env::attached_deposit(),
+            self.to_tera(20),
+        );
+
+        amount
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, PromiseResult, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    fn get_context(deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(1_000_000_000 * 60 * 60 * 24 * 10)
+            .attached_deposit(deposit);
+        builder
+    }
+
+    fn setup() -> Contract {
+        testing_env!(get_context(0).build(), VMConfig::free(), RuntimeFeesConfig::free());
+        let mut contract = Contract::default();
+        testing_env!(get_context(contract.to_yocto("0.01")).build(), VMConfig::free(), RuntimeFeesConfig::free());
+        contract.add_collection("Collection 1".to_string(), "image".to_string(), vec!["1-1".to_string()]);
+        let mine_id = contract.mint_mine_nft().token_id;
+        contract.mint_free_stone_nft(mine_id);
+        contract.mnl_reserve = MnlReserve { total: Balance::MAX, period: Balance::MAX, synced: true, ..Default::default() };
+        contract
+    }
+
+    #[test]
+    fn claim_once_for_completed_collection() {
+        let mut contract = setup();
+        let progress = contract.get_collections_progress(accounts(1).into(), None, None);
+        assert_eq!(progress.get(&1).unwrap().owned, 1);
+        assert!(progress.get(&1).unwrap().completed);
+
+        let reward = contract.claim_collection_reward(&accounts(1).into(), 1);
+        assert_eq!(reward, contract.collection_completion_reward);
+        assert!(contract.collection_progress(&accounts(1).into(), 1).reward_claimed);
+    }
+
+    #[test]
+    fn failed_bonus_kept_for_retry() {
+        let mut contract = setup();
+        testing_env!(
+            get_context(contract.to_yocto("0.01")).build(), VMConfig::free(), RuntimeFeesConfig::free(),
+            Default::default(), vec![PromiseResult::Failed]
+        );
+        let reward = contract.claim_collection_reward(&accounts(1).into(), 1);
+
+        assert!(!contract.on_mnl_reward_paid(accounts(1).into(), U128(reward)));
+        assert_eq!(contract.get_failed_payout(accounts(1)).0, reward);
+    }
+
+    #[test]
+    #[should_panic(expected = "Collection reward already claimed")]
+    fn claim_twice() {
+        let mut contract = setup();
+        contract.claim_collection_reward(&accounts(1).into(), 1);
+        contract.claim_collection_reward(&accounts(1).into(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Collection is not completed")]
+    fn claim_incomplete_collection() {
+        let mut contract = setup();
+        contract.update_collection(1, None, None, Some(vec!["1-1".to_string(), "1-2".to_string()]));
+        contract.claim_collection_reward(&accounts(1).into(), 1);
+    }
+}
@@ -0,0 +1,179 @@
+use near_sdk::PromiseResult;
+
+use crate::*;
+
+// Chance (percent) to get back a stone for each collection image
+pub const DISMANTLE_STONE_CHANCE: u32 = 60;
+
+impl Contract {
+    // Burn monster and mint part of its stones back with degraded attributes, `fee_paid` is MNL received
+    // with ft_transfer_call. Returns new stones and a promise with unused MNL amount.
+    pub(crate) fn dismantle(&mut self, owner_id: &AccountId, monster_id: TokenId, fee_paid: u128) -> (Vec<Stone>, Promise) {
+        let monster = self.assert_monster_owner(&monster_id, owner_id);
+        if !monster.equipment.is_empty() {
+            panic!("Unequip stones before dismantling");
+        }
+        let fee = self.monster_dismantle_fee;
+        if fee_paid < fee {
+            panic!("Dismantle fee is {} MNL yocto, pay it with ft_transfer_call", fee);
+        }
+
+        let collection = self.collections.get(&monster.collection_id).expect("Collection not found");
+        let image_count = collection.stone_images.len() as u32;
+        let mut random = self.random_stream(owner_id, &monster_id);
+
+        // Randomized losses, at least one stone is returned
+        let mut stone_indexes: Vec<u8> = (0..image_count)
+            .filter(|_| random.range(100) < DISMANTLE_STONE_CHANCE)
+            .map(|index| index as u8)
+            .collect();
+        if stone_indexes.is_empty() {
+            stone_indexes.push(random.range(image_count) as u8);
+        }
+
+        self.monster_unlink_from_user(&monster);
+
+        let drop_rates = self.mine_drop_rates(&MineType::Small);
+        let mut metadata = vec![];
+        let mut new_stones = vec![];
+        for (num, collection_index) in stone_indexes.into_iter().enumerate() {
+            let card_rarity = self.generate_stone_card_rarity(&drop_rates, &mut random);
+            let card_rarity = self.stone_rarity_with_supply(monster.collection_id, card_rarity);
+            let attributes = (
+                Self::degraded_attribute(monster.hardness, image_count, STONE_HARDNESS_RANGE, &mut random),
+                Self::degraded_attribute(monster.density, image_count, STONE_DENSITY_RANGE, &mut random),
+                Self::degraded_attribute(monster.durability, image_count, STONE_DURABILITY_RANGE, &mut random),
+            );
+            let (stone_metadata, stone) = self.create_stone(
+                owner_id, num as u32 + 1, monster.collection_id, card_rarity, attributes, collection_index,
+            );
+            metadata.push(stone_metadata);
+            new_stones.push(stone);
+        }
+
+        // Burn monster & mint stones in one nft-stone call, revert if it fails
+        let promise = Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"dismantle_monster".to_vec(),
+            json!({
+                "monster_id": monster_id,
+                "owner_id": owner_id,
+                "stones_metadata": metadata,
+            }).to_string().as_bytes().to_vec(),
+            self.to_yocto("0.01") * new_stones.len() as u128,
+            self.to_tera(20 + 8 * new_stones.len() as u64),
+        ).then(Promise::new(env::current_account_id()).function_call(
+            b"on_monster_dismantled".to_vec(),
+            json!({
+                "monster": monster,
+                "new_stones": new_stones,
+                "fee_paid": U128(fee_paid),
+                "fee": U128(fee),
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(20),
+        ));
+
+        (new_stones, promise)
+    }
+
+    // Average stone attribute of the monster minus random 0 or 1, limited by attribute range
+    fn degraded_attribute(total: u8, count: u32, (min, max): (u8, u8), random: &mut RandomStream) -> u8 {
+        let value = (total as u32 / count).saturating_sub(random.range(2));
+        value.clamp(min as u32, max as u32) as u8
+    }
+
+    pub(crate) fn monster_unlink_from_user(&mut self, monster: &Monster) {
+        let mut user_monsters = self.user_monsters.get(&monster.owner_id).unwrap();
+        let index = user_monsters.iter().position(|monster_id| &monster.token_id == monster_id).unwrap();
+        user_monsters.remove(index);
+        self.user_monsters.insert(&monster.owner_id, &user_monsters);
+
+        self.monsters.remove(&monster.token_id);
+    }
+
+    // Returns unused MNL fee amount
+    pub(crate) fn resolve_dismantle(&mut self, monster: Monster, new_stones: Vec<Stone>, fee_paid: u128, fee: u128) -> u128 {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            self.monster_battles.remove(&monster.token_id);
+            self.monster_ratings.remove(&monster.token_id);
+            self.burn_mnl(fee, "Monster dismantle");
+            return fee_paid - fee;
+        }
+
+        // Burn failed: drop new stones (they were never minted) and return monster
+        for stone in new_stones.iter() {
+            if self.stones.get(&stone.token_id).is_some() {
+                self.stone_unlink_from_user(stone, &monster.owner_id);
+                self.stone_supply_sub_minted(stone.collection_id, &stone.card_rarity);
+            }
+        }
+
+        let mut user_monsters = self.user_monsters.get(&monster.owner_id).unwrap_or(vec![]);
+        user_monsters.push(monster.token_id.to_string());
+        self.user_monsters.insert(&monster.owner_id, &user_monsters);
+        self.monsters.insert(&monster.token_id, &monster);
+
+        fee_paid
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, PromiseResult, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use crate::monster::test_utils::*;
+    use crate::SupplyScope;
+
+    #[test]
+    fn dismantle_returns_degraded_stones() {
+        let mut contract = setup_contract();
+        contract.update_collection(1, None, None, Some(vec!["1-1".to_string(), "1-2".to_string(), "1-3".to_string()]));
+        let monster_id = mint_test_monster_from_images(&mut contract, 2, 3);
+        let monster = contract.get_monster(monster_id.to_string());
+
+        set_context(2, 0);
+        let fee = contract.monster_dismantle_fee;
+        let (stones, _) = contract.dismantle(&accounts(2).into(), monster_id, fee);
+
+        assert!(!stones.is_empty() && stones.len() <= 3);
+        assert!(contract.user_monsters(accounts(2)).is_empty());
+        for stone in stones.iter() {
+            assert_eq!(stone.collection_id, 1);
+            assert!(stone.hardness <= (monster.hardness / 3).max(1));
+            assert!(stone.density <= (monster.density / 3).max(1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Dismantle fee is")]
+    fn dismantle_without_fee() {
+        let mut contract = setup_contract();
+        let monster_id = mint_test_monster(&mut contract, 2);
+        contract.dismantle(&accounts(2).into(), monster_id, 0);
+    }
+
+    #[test]
+    fn failed_burn_reverts_dismantle() {
+        let mut contract = setup_contract();
+        contract.update_collection(1, None, None, Some(vec!["1-1".to_string(), "1-2".to_string(), "1-3".to_string()]));
+        let monster_id = mint_test_monster_from_images(&mut contract, 2, 3);
+        let monster = contract.get_monster(monster_id.to_string());
+        let minted = contract.get_stone_supply(&SupplyScope::Collection(1)).minted;
+
+        set_context(2, 0);
+        let fee = contract.monster_dismantle_fee;
+        let (stones, _) = contract.dismantle(&accounts(2).into(), monster_id.to_string(), fee);
+        let stone_ids: Vec<String> = stones.iter().map(|stone| stone.token_id.to_string()).collect();
+
+        let mut builder = VMContextBuilder::new();
+        builder.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![PromiseResult::Failed]);
+        assert_eq!(contract.on_monster_dismantled(monster, stones, fee.into(), fee.into()).0, fee);
+
+        assert_eq!(contract.user_monsters(accounts(2)).len(), 1);
+        assert!(contract.get_stones_by_id(stone_ids).is_empty());
+        let supply = contract.get_stone_supply(&SupplyScope::Collection(1));
+        assert_eq!((supply.minted, supply.burned), (minted, 3));
+    }
+}
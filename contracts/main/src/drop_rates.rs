@@ -0,0 +1,76 @@
+use crate::*;
+
+// All drop rates are stored in basis points, 10000 = 100%
+pub const DROP_RATES_TOTAL: u32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropRates {
+    pub common: u32,
+    pub uncommon: u32,
+    pub rare: u32,
+    pub legendary: u32,
+}
+
+impl DropRates {
+    pub fn default_for(mine_type: &MineType) -> Self {
+        match mine_type {
+            MineType::Small => DropRates { common: 7000, uncommon: 2400, rare: 500, legendary: 100 },
+            MineType::Medium => DropRates { common: 6400, uncommon: 2880, rare: 600, legendary: 120 },
+            MineType::Large => DropRates { common: 5500, uncommon: 3600, rare: 750, legendary: 150 },
+        }
+    }
+
+    pub fn assert_valid(&self) {
+        let total = self.common as u64 + self.uncommon as u64 + self.rare as u64 + self.legendary as u64;
+        if total != DROP_RATES_TOTAL as u64 {
+            panic!("Drop rates should sum to 100%");
+        }
+    }
+
+    // Pick card rarity for random value from 0 to DROP_RATES_TOTAL-1
+    pub fn pick_rarity(&self, rand_val: u32) -> CardRarity {
+        let mut threshold = self.legendary;
+        if rand_val < threshold {
+            return CardRarity::Legendary;
+        }
+        threshold += self.rare;
+        if rand_val < threshold {
+            return CardRarity::Rare;
+        }
+        threshold += self.uncommon;
+        if rand_val < threshold {
+            return CardRarity::UnCommon;
+        }
+        CardRarity::Common
+    }
+
+    pub fn to_percentages(&self) -> HashMap<String, f64> {
+        let mut result = HashMap::new();
+        let percent = |value: u32| value as f64 * 100.0 / DROP_RATES_TOTAL as f64;
+        result.insert(CardRarity::Common.to_string(), percent(self.common));
+        result.insert(CardRarity::UnCommon.to_string(), percent(self.uncommon));
+        result.insert(CardRarity::Rare.to_string(), percent(self.rare));
+        result.insert(CardRarity::Legendary.to_string(), percent(self.legendary));
+        result
+    }
+}
+
+impl Contract {
+    pub(crate) fn mine_drop_rates(&self, mine_type: &MineType) -> DropRates {
+        self.drop_rates.get(mine_type).unwrap()
+    }
+
+    pub(crate) fn update_drop_rates(&mut self, mine_type: MineType, rates: DropRates) {
+        rates.assert_valid();
+
+        let old_rates = self.mine_drop_rates(&mine_type);
+        self.drop_rates.insert(&mine_type, &rates);
+
+        EventLog::new(EventLogVariant::DropRatesUpdate(vec![DropRatesUpdateLog {
+            mine_type: mine_type.to_string(),
+            old_rates,
+            new_rates: rates,
+        }])).emit();
+    }
+}
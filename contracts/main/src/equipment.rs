@@ -0,0 +1,200 @@
+use crate::*;
+
+pub const MONSTER_EQUIPMENT_SLOTS: usize = 3;
+// XP for level L is MONSTER_LEVEL_XP * L * (L - 1) / 2: 100 for level 2, 300 for level 3, ...
+pub const MONSTER_LEVEL_XP: u32 = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MonsterStats {
+    pub level: u32,
+    pub xp: u32,
+    pub next_level_xp: u32,
+    pub hardness: u32,
+    pub density: u32,
+    pub durability: u32,
+    pub battle_power: u32,
+    pub equipment: Vec<Stone>,
+}
+
+impl Monster {
+    pub fn level_xp(level: u32) -> u32 {
+        MONSTER_LEVEL_XP * level * (level - 1) / 2
+    }
+
+    pub fn level_for_xp(xp: u32) -> u32 {
+        let mut level = 1;
+        while xp >= Self::level_xp(level + 1) {
+            level += 1;
+        }
+        level
+    }
+
+    // Monster NFT `extra` metadata: level and gear
+    pub fn metadata_extra(&self) -> String {
+        json!({
+            "level": self.level,
+            "xp": self.xp,
+            "hardness": self.hardness as u32 + self.bonus_hardness,
+            "density": self.density as u32 + self.bonus_density,
+            "durability": self.durability as u32 + self.bonus_durability,
+            "equipment": self.equipment,
+        }).to_string()
+    }
+}
+
+impl Contract {
+    pub(crate) fn equip_stone(&mut self, owner_id: &AccountId, monster_id: TokenId, stone_id: TokenId) {
+        if env::attached_deposit() < self.to_yocto(STAKE_LOCK_DEPOSIT) {
+            panic!("Attach {} NEAR to lock the stone", STAKE_LOCK_DEPOSIT);
+        }
+        let mut monster = self.assert_monster_owner(&monster_id, owner_id);
+        if monster.equipment.len() >= MONSTER_EQUIPMENT_SLOTS {
+            panic!("All equipment slots are taken");
+        }
+
+        let user_stones = self.user_stones.get(owner_id).unwrap_or(vec![]);
+        if !user_stones.contains(&stone_id) {
+            panic!("You don't own this stone");
+        }
+        self.assert_stone_unlocked(&stone_id);
+        if self.market.get(&stone_id).is_some() {
+            panic!("Remove stone from market before equipping");
+        }
+
+        let stone = self.stones.get(&stone_id).unwrap();
+        monster.bonus_hardness += stone.hardness as u32;
+        monster.bonus_density += stone.density as u32;
+        monster.bonus_durability += stone.durability as u32;
+        monster.equipment.push(stone_id.to_string());
+        self.monsters.insert(&monster_id, &monster);
+        self.equipped_stones.insert(&stone_id, &monster_id);
+
+        // Lock stone on NFT contract
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_lock_tokens".to_vec(),
+            json!({
+                "token_ids": vec![stone_id],
+                "owner_id": owner_id,
+            }).to_string().as_bytes().to_vec(),
+            env::attached_deposit(),
+            self.to_tera(10),
+        );
+        self.update_monster_metadata(&monster);
+    }
+
+    pub(crate) fn unequip_stone(&mut self, owner_id: &AccountId, monster_id: TokenId, stone_id: TokenId) {
+        let mut monster = self.assert_monster_owner(&monster_id, owner_id);
+        let index = monster.equipment.iter().position(|id| id == &stone_id).expect("Stone is not equipped");
+        monster.equipment.remove(index);
+
+        let stone = self.stones.get(&stone_id).unwrap();
+        monster.bonus_hardness -= stone.hardness as u32;
+        monster.bonus_density -= stone.density as u32;
+        monster.bonus_durability -= stone.durability as u32;
+        self.monsters.insert(&monster_id, &monster);
+        self.equipped_stones.remove(&stone_id);
+
+        // Unlock stone on NFT contract
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_unlock_tokens".to_vec(),
+            json!({
+                "token_ids": vec![stone_id],
+                "owner_id": owner_id,
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(10),
+        );
+        self.update_monster_metadata(&monster);
+    }
+
+    // Update level from XP, returns true on level up
+    pub(crate) fn monster_level_up(&self, monster: &mut Monster) -> bool {
+        let level = Monster::level_for_xp(monster.xp);
+        if level == monster.level {
+            return false;
+        }
+        monster.level = level;
+        true
+    }
+
+    pub(crate) fn update_monster_metadata(&self, monster: &Monster) {
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_update_extra".to_vec(),
+            json!({
+                "token_id": monster.token_id,
+                "extra": monster.metadata_extra(),
+            }).to_string().as_bytes().to_vec(),
+            self.to_yocto("0.001"),
+            self.to_tera(10),
+        );
+    }
+
+    pub(crate) fn monster_stats(&self, monster_id: &TokenId) -> MonsterStats {
+        let monster = self.monsters.get(monster_id).expect("Monster not found");
+        MonsterStats {
+            level: monster.level,
+            xp: monster.xp,
+            next_level_xp: Monster::level_xp(monster.level + 1),
+            hardness: monster.hardness as u32 + monster.bonus_hardness,
+            density: monster.density as u32 + monster.bonus_density,
+            durability: monster.durability as u32 + monster.bonus_durability,
+            battle_power: monster.battle_power(),
+            equipment: self.get_stones_by_id(monster.equipment.to_vec()),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    fn equip_context(contract: &Contract) {
+        set_context(2, contract.to_yocto(STAKE_LOCK_DEPOSIT));
+    }
+
+    #[test]
+    fn level_for_xp() {
+        assert_eq!(Monster::level_for_xp(0), 1);
+        assert_eq!(Monster::level_for_xp(99), 1);
+        assert_eq!(Monster::level_for_xp(100), 2);
+        assert_eq!(Monster::level_for_xp(300), 3);
+        assert_eq!(Monster::level_for_xp(599), 3);
+    }
+
+    #[test]
+    fn equip_and_unequip() {
+        let mut contract = setup_contract();
+        let monster_id = mint_test_monster_from_images(&mut contract, 2, 1);
+        let (_, stone) = contract.create_stone(&accounts(2).into(), 9, 1, CardRarity::Common, (3, 2, 1), 0);
+        let base_power = contract.get_monster(monster_id.to_string()).battle_power();
+
+        equip_context(&contract);
+        contract.equip_monster_stone(monster_id.to_string(), stone.token_id.to_string());
+        let stats = contract.get_monster_stats(monster_id.to_string());
+        assert_eq!((stats.hardness, stats.density, stats.durability), (5, 4, 2));
+        assert_eq!(stats.battle_power, base_power + 3 * 3 + 2 * 2 + 2);
+        assert_eq!(stats.equipment.len(), 1);
+
+        set_context(2, 1);
+        contract.unequip_monster_stone(monster_id.to_string(), stone.token_id);
+        assert_eq!(contract.get_monster_stats(monster_id).battle_power, base_power);
+    }
+
+    #[test]
+    #[should_panic(expected = "Stone is equipped")]
+    fn equipped_stone_is_locked() {
+        let mut contract = setup_contract();
+        let monster_id = mint_test_monster_from_images(&mut contract, 2, 1);
+        let (_, stone) = contract.create_stone(&accounts(2).into(), 9, 1, CardRarity::Common, (3, 2, 1), 0);
+
+        equip_context(&contract);
+        contract.equip_monster_stone(monster_id, stone.token_id.to_string());
+        set_context(2, 1);
+        contract.stone_kill(stone.token_id);
+    }
+}
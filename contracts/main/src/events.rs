@@ -0,0 +1,144 @@
+use crate::*;
+
+/// This is the name of the standard used for game events emitted by the main contract
+pub const EVENT_STANDARD_NAME: &str = "mineland";
+/// Version of the game events standard
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    DropRatesUpdate(Vec<DropRatesUpdateLog>),
+    CollectionUpdate(Vec<CollectionUpdateLog>),
+    CollectionStatus(Vec<CollectionStatusLog>),
+    MonsterBattle(Vec<MonsterBattleLog>),
+    ArenaBattle(Vec<ArenaBattleLog>),
+    TournamentFinish(Vec<TournamentFinishLog>),
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. mineland
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl EventLog {
+    pub fn new(event: EventLogVariant) -> Self {
+        Self {
+            standard: EVENT_STANDARD_NAME.to_string(),
+            version: EVENT_STANDARD_VERSION.to_string(),
+            event,
+        }
+    }
+
+    // Write the event to the transaction logs
+    pub fn emit(&self) {
+        env::log(self.to_string().as_bytes());
+    }
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &near_sdk::serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture drop rates changes
+///
+/// Arguments
+/// * `mine_type`: "Small"
+/// * `old_rates`: drop rates before the update (basis points)
+/// * `new_rates`: drop rates after the update (basis points)
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropRatesUpdateLog {
+    pub mine_type: String,
+    pub old_rates: DropRates,
+    pub new_rates: DropRates,
+}
+
+/// An event log to capture collection changes
+///
+/// Arguments
+/// * `collection_id`: 1
+/// * `old_collection`: collection before the update
+/// * `new_collection`: collection after the update
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionUpdateLog {
+    pub collection_id: u32,
+    pub old_collection: Collection,
+    pub new_collection: Collection,
+}
+
+/// An event log to capture collection (de)activation
+///
+/// Arguments
+/// * `collection_id`: 1
+/// * `active`: false when collection is removed from the drop pool
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionStatusLog {
+    pub collection_id: u32,
+    pub active: bool,
+}
+
+/// An event log to capture PvE battle results
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `monster_id`: "mn-1-1"
+/// * `result`: stage, rolls, reward and XP of the battle
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MonsterBattleLog {
+    pub owner_id: AccountId,
+    pub monster_id: TokenId,
+    pub result: BattleResult,
+}
+
+/// An event log to capture arena fights
+///
+/// Arguments
+/// * `challenge_id`: 1
+/// * `challenge`: accepted challenge
+/// * `result`: winner, prize and fee
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ArenaBattleLog {
+    pub challenge_id: u64,
+    pub challenge: ArenaChallenge,
+    pub result: ArenaResult,
+}
+
+/// An event log to capture tournament prizes
+///
+/// Arguments
+/// * `tournament_id`: 1
+/// * `standings`: paid places, winner first
+/// * `prizes`: MNL prize for each paid place
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentFinishLog {
+    pub tournament_id: u64,
+    pub standings: Vec<TournamentEntry>,
+    pub prizes: Vec<U128>,
+}
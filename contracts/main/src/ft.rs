@@ -1,20 +1,308 @@
+use near_sdk::PromiseResult;
+
 use crate::*;
 
+// Minimum storage deposit of the token account, charged from players whose registration main pays
+pub const FT_STORAGE_DEPOSIT: &str = "0.00125";
+
+// Part of storage_balance_bounds result of the token
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+}
+
 impl Contract {
-    pub(crate) fn add_token_storage(&mut self, account_id: &AccountId) {
-        let ft_mint_deposit: Balance = self.to_yocto("0.001");
-        let ft_mint_gas: Gas = self.to_tera(10);
+    // Register account storage on the token with the deposit from storage_balance_bounds,
+    // account is marked registered in on_ft_storage_registered
+    pub(crate) fn add_token_storage(&mut self, account_id: &AccountId) -> Promise {
+        self.ft_storage_pending.insert(account_id);
 
         Promise::new(self.contract_ft.clone()).function_call(
+            b"storage_balance_bounds".to_vec(),
+            b"{}".to_vec(),
+            0,
+            self.to_tera(5),
+        ).then(Promise::new(env::current_account_id()).function_call(
+            b"on_ft_storage_bounds".to_vec(),
+            json!({
+                "account_id": account_id,
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(25),
+        ))
+    }
+
+    // Register account if it is not registered and registration is not in progress,
+    // returns registration promise to chain payouts after it
+    pub(crate) fn ensure_token_storage(&mut self, account_id: &AccountId) -> Option<Promise> {
+        if !self.token_storage_required(account_id) {
+            return None;
+        }
+        Some(self.add_token_storage(account_id))
+    }
+
+    // Account is not registered and registration is not in progress
+    pub(crate) fn token_storage_required(&self, account_id: &AccountId) -> bool {
+        !self.ft_storage_accounts.contains(account_id) && !self.ft_storage_pending.contains(account_id)
+    }
+
+    // Run payout after registration of the receiver if needed
+    pub(crate) fn with_token_storage(&mut self, account_id: &AccountId, payout: Promise) -> Promise {
+        match self.ensure_token_storage(account_id) {
+            Some(registration) => registration.then(payout),
+            None => payout,
+        }
+    }
+
+    // Send storage_deposit with minimum deposit from the token storage_balance_bounds result
+    pub(crate) fn resolve_ft_storage_bounds(&mut self, account_id: &AccountId) -> PromiseOrValue<bool> {
+        let bounds: StorageBalanceBounds = match env::promise_result(0) {
+            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice(&value).expect("Wrong storage bounds"),
+            _ => {
+                self.ft_storage_pending.remove(account_id);
+                return PromiseOrValue::Value(false);
+            }
+        };
+
+        PromiseOrValue::Promise(Promise::new(self.contract_ft.clone()).function_call(
+            b"storage_deposit".to_vec(),
+            json!({
+                "account_id": account_id,
+                "registration_only": true
+            }).to_string().as_bytes().to_vec(),
+            bounds.min.into(),
+            self.to_tera(10),
+        ).then(Promise::new(env::current_account_id()).function_call(
+            b"on_ft_storage_registered".to_vec(),
+            json!({
+                "account_id": account_id,
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(5),
+        )))
+    }
+
+    pub(crate) fn resolve_ft_storage_registered(&mut self, account_id: &AccountId) -> bool {
+        self.ft_storage_pending.remove(account_id);
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            self.ft_storage_accounts.insert(account_id);
+            return true;
+        }
+        false
+    }
+
+    // Forget registration state and register accounts again
+    pub(crate) fn repair_token_storage(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in account_ids.iter() {
+            self.ft_storage_accounts.remove(account_id);
+            self.add_token_storage(account_id);
+        }
+    }
+
+    // Mint new MNL tokens, main contract must be a minter of the token.
+    // New receiver is registered first, ft_mint is called without storage deposit.
+    pub(crate) fn mint_mnl(&mut self, receiver_id: &AccountId, amount: Balance, memo: &str) -> Promise {
+        let mint = Promise::new(self.contract_ft.to_string()).function_call(
             b"ft_mint".to_vec(),
             json!({
-            "receiver_id": account_id,
-            "amount": "0"
-        }).to_string().as_bytes().to_vec(),
-            ft_mint_deposit,
-            ft_mint_gas,
+                "receiver_id": receiver_id,
+                "amount": amount.to_string(),
+                "memo": memo
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(10),
         );
 
-        self.ft_storage_accounts.insert(account_id);
+        self.with_token_storage(receiver_id, mint)
+    }
+
+    // Burn MNL tokens received as payment from main contract balance
+    pub(crate) fn burn_mnl(&mut self, amount: Balance, memo: &str) {
+        if amount == 0 {
+            return;
+        }
+
+        Promise::new(self.contract_ft.to_string()).function_call(
+            b"ft_burn_from".to_vec(),
+            json!({
+                "amount": amount.to_string(),
+                "memo": memo
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(10),
+        );
+    }
+
+    // Transfer MNL tokens from main contract balance, new receiver is registered first
+    pub(crate) fn transfer_mnl(&mut self, receiver_id: &AccountId, amount: Balance) -> Promise {
+        let transfer = Promise::new(self.contract_ft.to_string()).function_call(
+            b"ft_transfer".to_vec(),
+            json!({
+                "receiver_id": receiver_id,
+                "amount": amount.to_string()
+            }).to_string().as_bytes().to_vec(),
+            1,
+            self.to_tera(10),
+        );
+        self.with_token_storage(receiver_id, transfer)
+    }
+}
+
+// Game actions paid in MNL with `ft_transfer_call`, `msg` is JSON e.g. {"action":"mint_mine","tier":"Medium"}
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", tag = "action", rename_all = "snake_case")]
+pub enum FtAction {
+    FuseStones { stone_ids: Vec<TokenId> },
+    RerollStoneAttribute { stone_id: TokenId, attribute: StoneAttribute },
+    ArenaChallenge { monster_id: TokenId, opponent_monster_id: TokenId },
+    ArenaAccept { challenge_id: u64 },
+    TournamentJoin { tournament_id: u64, monster_id: TokenId },
+    DismantleMonster { monster_id: TokenId },
+    GovernanceLock,
+    MintMine { tier: MineType },
+    SpeedUpClaim { mine_id: TokenId },
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    fn ft_on_transfer(&mut self, sender_id: ValidAccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        if env::predecessor_account_id() != self.contract_ft {
+            panic!("Only MNL tokens are accepted");
+        }
+
+        let action: FtAction = near_sdk::serde_json::from_str(&msg).expect("Wrong action message");
+        let sender_id: AccountId = sender_id.into();
+        match action {
+            FtAction::FuseStones { stone_ids } => {
+                let (_, promise) = self.fuse(&sender_id, stone_ids, amount.into());
+                PromiseOrValue::Promise(promise)
+            }
+            FtAction::RerollStoneAttribute { stone_id, attribute } => {
+                let unused = self.reroll_stone_attribute(&sender_id, stone_id, attribute, amount.into());
+                PromiseOrValue::Value(U128(unused))
+            }
+            FtAction::ArenaChallenge { monster_id, opponent_monster_id } => {
+                self.arena_challenge(&sender_id, monster_id, opponent_monster_id, amount.into());
+                PromiseOrValue::Value(U128(0))
+            }
+            FtAction::ArenaAccept { challenge_id } => {
+                let (_, unused) = self.arena_accept(&sender_id, challenge_id, amount.into());
+                PromiseOrValue::Value(U128(unused))
+            }
+            FtAction::TournamentJoin { tournament_id, monster_id } => {
+                let unused = self.tournament_join(&sender_id, tournament_id, monster_id, amount.into());
+                PromiseOrValue::Value(U128(unused))
+            }
+            FtAction::DismantleMonster { monster_id } => {
+                let (_, promise) = self.dismantle(&sender_id, monster_id, amount.into());
+                PromiseOrValue::Promise(promise)
+            }
+            FtAction::GovernanceLock => {
+                self.governance_lock(&sender_id, amount.into());
+                PromiseOrValue::Value(U128(0))
+            }
+            FtAction::MintMine { tier } => {
+                let unused = self.buy_mine(&sender_id, tier, amount.into());
+                PromiseOrValue::Value(U128(unused))
+            }
+            FtAction::SpeedUpClaim { mine_id } => {
+                let unused = self.speed_up_claim(&sender_id, mine_id, amount.into());
+                PromiseOrValue::Value(U128(unused))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    fn set_callback_context(result: PromiseResult) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![result]);
+    }
+
+    #[test]
+    fn storage_registered_only_on_success() {
+        set_callback_context(PromiseResult::Failed);
+        let mut contract = Contract::default();
+        contract.on_ft_storage_registered(accounts(2).into());
+        assert!(!contract.ft_storage_accounts.contains(&accounts(2).into()));
+
+        set_callback_context(PromiseResult::Successful(vec![]));
+        contract.on_ft_storage_registered(accounts(2).into());
+        assert!(contract.ft_storage_accounts.contains(&accounts(2).into()));
+    }
+
+    #[test]
+    fn storage_registration_sent_once() {
+        set_callback_context(PromiseResult::Successful(vec![]));
+        let mut contract = Contract::default();
+        assert!(contract.ensure_token_storage(&accounts(2).into()).is_some());
+        assert!(contract.ensure_token_storage(&accounts(2).into()).is_none());
+
+        // registration is retried after failed storage_balance_bounds
+        set_callback_context(PromiseResult::Failed);
+        match contract.on_ft_storage_bounds(accounts(2).into()) {
+            PromiseOrValue::Value(registered) => assert!(!registered),
+            _ => panic!("Expected failed registration"),
+        }
+        assert!(contract.ensure_token_storage(&accounts(2).into()).is_some());
+
+        set_callback_context(PromiseResult::Successful(br#"{"min":"2500000000000000000000","max":null}"#.to_vec()));
+        assert!(matches!(contract.on_ft_storage_bounds(accounts(2).into()), PromiseOrValue::Promise(_)));
+    }
+
+    #[test]
+    fn repair_storage_unmarks_account() {
+        set_callback_context(PromiseResult::Successful(vec![]));
+        let mut contract = Contract::default();
+        contract.on_ft_storage_registered(accounts(2).into());
+
+        contract.repair_ft_storage(vec![accounts(2)]);
+        assert!(!contract.ft_storage_accounts.contains(&accounts(2).into()));
+    }
+
+    fn set_token_context(predecessor: &str) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(ValidAccountId::try_from(predecessor).unwrap());
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+    }
+
+    #[test]
+    fn mnl_payment_routed_to_action() {
+        set_token_context("alice");
+        let mut contract = Contract::default();
+        contract.add_mine_mint_deposit(&accounts(2).into(), contract.to_yocto("0.02"));
+
+        set_token_context("ft.alice");
+        let price = contract.mine_mnl_price(&MineType::Large).unwrap();
+        let msg = r#"{"action":"mint_mine","tier":"Large"}"#.to_string();
+        match contract.ft_on_transfer(accounts(2), U128(price + 3), msg) {
+            PromiseOrValue::Value(unused) => assert_eq!(unused.0, 3),
+            _ => panic!("Expected unused amount"),
+        }
+        assert_eq!(contract.user_mines(accounts(2))[0].mine_type, MineType::Large);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only MNL tokens are accepted")]
+    fn other_tokens_rejected() {
+        set_token_context("alice");
+        let mut contract = Contract::default();
+
+        set_token_context("usdc.alice");
+        contract.ft_on_transfer(accounts(2), U128(1), r#"{"action":"governance_lock"}"#.to_string());
     }
 }
@@ -0,0 +1,211 @@
+use near_sdk::PromiseResult;
+
+use crate::*;
+
+// Burn `input_count` stones of one rarity to get one stone of the next rarity
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FusionRecipe {
+    pub input_count: u8,
+    // MNL fee, paid with ft_transfer_call when not zero
+    pub fee: U128,
+}
+
+impl FusionRecipe {
+    pub fn default_for(card_rarity: &CardRarity) -> Option<Self> {
+        let input_count = match card_rarity {
+            CardRarity::Common => 5,
+            CardRarity::UnCommon => 4,
+            CardRarity::Rare => 3,
+            CardRarity::Legendary => return None,
+        };
+        Some(FusionRecipe { input_count, fee: U128(0) })
+    }
+}
+
+impl Contract {
+    pub(crate) fn update_fusion_recipe(&mut self, card_rarity: CardRarity, recipe: Option<FusionRecipe>) {
+        if card_rarity.higher().is_none() {
+            panic!("Legendary stones can't be fused");
+        }
+        match recipe {
+            Some(recipe) => {
+                if recipe.input_count < 2 {
+                    panic!("Fusion needs at least 2 stones");
+                }
+                self.fusion_recipes.insert(&card_rarity, &recipe);
+            }
+            None => {
+                self.fusion_recipes.remove(&card_rarity);
+            }
+        }
+    }
+
+    // Burn input stones and mint fused stone, `fee_paid` is MNL amount received with ft_transfer_call.
+    // Returns fused stone and a promise with unused MNL amount (all of it if the mint was reverted).
+    pub(crate) fn fuse(&mut self, owner_id: &AccountId, stone_ids: Vec<TokenId>, fee_paid: u128) -> (Stone, Promise) {
+        let input_stones = self.get_stones_by_id(stone_ids.to_vec());
+        if input_stones.len() != stone_ids.len() || input_stones.is_empty() {
+            panic!("Stone not found");
+        }
+
+        let card_rarity = input_stones[0].card_rarity.clone();
+        let recipe = self.fusion_recipes.get(&card_rarity).expect("No fusion recipe for this rarity");
+        if input_stones.len() != recipe.input_count as usize {
+            panic!("Fusion requires {} stones", recipe.input_count);
+        }
+        if fee_paid < recipe.fee.0 {
+            panic!("Fusion fee is {} MNL yocto, pay it with ft_transfer_call", recipe.fee.0);
+        }
+
+        for stone in input_stones.iter() {
+            if stone.card_rarity != card_rarity {
+                panic!("All stones should have the same rarity");
+            }
+            if self.market.get(&stone.token_id).is_some() {
+                panic!("Remove stone from market before fusion");
+            }
+            // checks owner & locks
+            self.stone_remove_from_user(stone, owner_id);
+        }
+
+        let new_rarity = card_rarity.higher().unwrap();
+        let mut random = self.random_stream(owner_id, &stone_ids[0]);
+        let collection_id = input_stones[random.range(input_stones.len() as u32) as usize].collection_id;
+        if !self.stone_supply_available(collection_id, &new_rarity) {
+            panic!("Stone supply is exhausted");
+        }
+
+        let attributes = (
+            Self::fused_attribute(input_stones.iter().map(|stone| stone.hardness), STONE_HARDNESS_RANGE, &mut random),
+            Self::fused_attribute(input_stones.iter().map(|stone| stone.density), STONE_DENSITY_RANGE, &mut random),
+            Self::fused_attribute(input_stones.iter().map(|stone| stone.durability), STONE_DURABILITY_RANGE, &mut random),
+        );
+        let collection_index = self.generate_stone_index(collection_id, &mut random);
+        let (metadata, new_stone) = self.create_stone(owner_id, 1, collection_id, new_rarity, attributes, collection_index);
+
+        // Mint new stone & burn inputs in one nft-stone call, revert fusion if it fails
+        let promise = Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"mint_fused_stone".to_vec(),
+            json!({
+                "stone_data": metadata,
+                "input_stones": stone_ids,
+            }).to_string().as_bytes().to_vec(),
+            self.to_yocto("0.01"),
+            self.to_tera(20 + 5 * stone_ids.len() as u64),
+        ).then(Promise::new(env::current_account_id()).function_call(
+            b"on_stones_fused".to_vec(),
+            json!({
+                "owner_id": owner_id,
+                "new_stone": new_stone,
+                "input_stones": input_stones,
+                "fee_paid": U128(fee_paid),
+                "fee": recipe.fee,
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(20),
+        ));
+
+        (new_stone, promise)
+    }
+
+    // Average of input attributes (rounded up) plus random 0 or 1, limited by attribute range
+    fn fused_attribute<I: Iterator<Item=u8>>(values: I, (min, max): (u8, u8), random: &mut RandomStream) -> u8 {
+        let values: Vec<u32> = values.map(|value| value as u32).collect();
+        let count = values.len() as u32;
+        let average = values.iter().sum::<u32>().div_ceil(count);
+        let value = average + random.range(2);
+        value.clamp(min as u32, max as u32) as u8
+    }
+
+    // Returns unused MNL fee amount
+    pub(crate) fn resolve_fusion(
+        &mut self,
+        owner_id: AccountId,
+        new_stone: Stone,
+        input_stones: Vec<Stone>,
+        fee_paid: u128,
+        fee: u128,
+    ) -> u128 {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            self.burn_mnl(fee, "Stone fusion");
+            return fee_paid - fee;
+        }
+
+        // Mint failed: drop fused stone (it was never minted) and return input stones
+        if self.stones.get(&new_stone.token_id).is_some() {
+            self.stone_unlink_from_user(&new_stone, &owner_id);
+            self.stone_supply_sub_minted(new_stone.collection_id, &new_stone.card_rarity);
+        }
+
+        for stone in input_stones.iter() {
+            self.stone_add_to_user(stone, &owner_id);
+            self.stone_killed_count -= 1;
+            self.stone_supply_sub_burned(stone.collection_id, &stone.card_rarity);
+        }
+
+        fee_paid
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    fn setup() -> Contract {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(accounts(0));
+        testing_env!(builder.build());
+        Contract::default()
+    }
+
+    #[test]
+    fn fused_attribute_in_range() {
+        setup();
+        let mut random = RandomStream::new(&[7u8; 32], "alice", "zm-1-1", 1);
+        for _ in 0..50 {
+            let value = Contract::fused_attribute(vec![6u8, 6, 5].into_iter(), STONE_HARDNESS_RANGE, &mut random);
+            assert_eq!(value, 6);
+            let value = Contract::fused_attribute(vec![1u8, 2].into_iter(), STONE_HARDNESS_RANGE, &mut random);
+            assert!((2..=3).contains(&value));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Legendary stones can't be fused")]
+    fn legendary_recipe() {
+        let mut contract = setup();
+        contract.set_fusion_recipe(CardRarity::Legendary, Some(FusionRecipe { input_count: 2, fee: U128(0) }));
+    }
+
+    #[test]
+    fn failed_mint_reverts_fusion() {
+        let mut contract = setup_contract();
+        contract.set_rarity_supply_cap(CardRarity::UnCommon, Some(1));
+        let owner_id: AccountId = accounts(2).into();
+        let stone_ids: Vec<TokenId> = (0..5).map(|num| {
+            contract.create_stone(&owner_id, num + 1, 1, CardRarity::Common, (2, 2, 1), 0).1.token_id
+        }).collect();
+
+        let input_stones = contract.get_stones_by_id(stone_ids.to_vec());
+        let (new_stone, _) = contract.fuse(&owner_id, stone_ids, 0);
+        let new_stone_id = new_stone.token_id.to_string();
+        assert_eq!(contract.stone_killed_count, 5);
+
+        let mut builder = VMContextBuilder::new();
+        builder.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![PromiseResult::Failed]);
+        assert_eq!(contract.on_stones_fused(owner_id.to_string(), new_stone, input_stones, U128(7), U128(0)).0, 7);
+
+        assert!(contract.stones.get(&new_stone_id).is_none());
+        assert_eq!(contract.stone_killed_count, 0);
+        let supply = contract.get_stone_supply(&SupplyScope::Rarity(CardRarity::UnCommon));
+        assert_eq!((supply.minted, supply.burned, supply.remaining), (0, 0, Some(1)));
+    }
+}
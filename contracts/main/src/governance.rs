@@ -0,0 +1,385 @@
+use near_sdk::PromiseResult;
+
+use crate::*;
+use crate::market::MARKET_FEE_MAX_BPS;
+
+pub const GOVERNANCE_PROPOSALS_DEFAULT_LIMIT: u64 = 50;
+
+// Game parameters which can be changed by proposals
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", rename_all = "snake_case")]
+pub enum GovernanceChange {
+    DropRates { mine_type: MineType, rates: DropRates },
+    MinePrice { mine_type: MineType, price: U128 },
+    // Part of market sale price (basis points) kept by the contract
+    MarketFee { fee_bps: u32 },
+    // MNL added to kill reward for each doubling of stone age
+    KillReward { reward: U128 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalStatus {
+    Voting,
+    Executed,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceConfig {
+    // Part of locked MNL (basis points) which should vote
+    pub quorum_bps: u32,
+    // Votes required regardless of locked MNL
+    pub min_quorum: U128,
+    pub voting_period: U64,
+    // Delay between end of voting and execution
+    pub timelock: U64,
+    // Voting power required to create proposal
+    pub proposal_threshold: U128,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    pub proposer_id: AccountId,
+    pub description: String,
+    pub change: GovernanceChange,
+    pub quorum: U128,
+    pub votes_for: U128,
+    pub votes_against: U128,
+    pub voting_ends_at: U64,
+    pub executable_at: U64,
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceVoter {
+    pub power: U128,
+    // Tokens can't be unlocked before the end of voting on proposals of the account
+    pub unlock_at: U64,
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        Self {
+            quorum_bps: 1_000, // 10%
+            min_quorum: U128(1_000_000_000_000_000_000_000_000_000), // 1000 MNL
+            voting_period: U64(3 * 24 * 60 * 60 * 1_000_000_000), // 3 days
+            timelock: U64(24 * 60 * 60 * 1_000_000_000), // 1 day
+            proposal_threshold: U128(100_000_000_000_000_000_000_000_000), // 100 MNL
+        }
+    }
+}
+
+impl Proposal {
+    pub fn approved(&self) -> bool {
+        self.votes_for.0 + self.votes_against.0 >= self.quorum.0 && self.votes_for.0 > self.votes_against.0
+    }
+}
+
+impl Contract {
+    pub(crate) fn governance_power(&self, account_id: &AccountId) -> Balance {
+        self.governance_locks.get(account_id).unwrap_or(0)
+    }
+
+    // Lock MNL received with ft_transfer_call as voting power
+    pub(crate) fn governance_lock(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            panic!("Amount should be positive");
+        }
+        self.governance_locks.insert(account_id, &(self.governance_power(account_id) + amount));
+        self.governance_locked_total += amount;
+    }
+
+    // Quorum for current locked MNL, not less than the minimum quorum
+    pub(crate) fn governance_quorum(&self) -> Balance {
+        let config = &self.governance_config;
+        (self.governance_locked_total / 10_000 * config.quorum_bps as u128).max(config.min_quorum.0)
+    }
+
+    pub(crate) fn governance_unlock_tokens(&mut self, account_id: &AccountId, amount: Balance) {
+        let power = self.governance_power(account_id);
+        if amount == 0 || amount > power {
+            panic!("Wrong unlock amount");
+        }
+        if self.governance_unlock_at.get(account_id).unwrap_or(0) > env::block_timestamp() {
+            panic!("Tokens are locked until the end of voting");
+        }
+
+        self.governance_locks.insert(account_id, &(power - amount));
+        self.governance_locked_total -= amount;
+        // lock is restored in on_governance_unlocked if transfer fails
+        self.transfer_mnl(account_id, amount).then(Promise::new(env::current_account_id()).function_call(
+            b"on_governance_unlocked".to_vec(),
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(5),
+        ));
+    }
+
+    pub(crate) fn resolve_governance_unlock(&mut self, account_id: &AccountId, amount: Balance) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        self.governance_lock(account_id, amount);
+        false
+    }
+
+    // Keep voter tokens locked until the end of voting
+    fn governance_extend_lock(&mut self, account_id: &AccountId, timestamp: u64) {
+        if self.governance_unlock_at.get(account_id).unwrap_or(0) < timestamp {
+            self.governance_unlock_at.insert(account_id, &timestamp);
+        }
+    }
+
+    pub(crate) fn assert_governance_change_valid(&self, change: &GovernanceChange) {
+        match change {
+            GovernanceChange::DropRates { rates, .. } => rates.assert_valid(),
+            GovernanceChange::MinePrice { mine_type, price } => {
+                // mine type is detected by attached deposit, so prices should differ
+                let used = MineType::all().iter()
+                    .any(|other| other != mine_type && Mine::mine_prices(other, self) == price.0);
+                if price.0 == 0 || used {
+                    panic!("Wrong mine price");
+                }
+            }
+            GovernanceChange::MarketFee { fee_bps } => {
+                if *fee_bps > MARKET_FEE_MAX_BPS {
+                    panic!("Fee can't be more than 100%");
+                }
+            }
+            GovernanceChange::KillReward { .. } => {}
+        }
+    }
+
+    pub(crate) fn create_governance_proposal(
+        &mut self,
+        proposer_id: &AccountId,
+        description: String,
+        change: GovernanceChange,
+    ) -> u64 {
+        let config = self.governance_config.clone();
+        if self.governance_power(proposer_id) < config.proposal_threshold.0 {
+            panic!("Not enough voting power to create proposal");
+        }
+        self.assert_governance_change_valid(&change);
+
+        let voting_ends_at = env::block_timestamp() + config.voting_period.0;
+        // current quorum, final one is computed at the end of voting
+        let quorum = self.governance_quorum();
+        self.proposal_count += 1;
+        self.proposals.insert(&self.proposal_count, &Proposal {
+            proposer_id: proposer_id.to_string(),
+            description,
+            change,
+            quorum: quorum.into(),
+            votes_for: U128(0),
+            votes_against: U128(0),
+            voting_ends_at: voting_ends_at.into(),
+            executable_at: (voting_ends_at + config.timelock.0).into(),
+            status: ProposalStatus::Voting,
+        });
+        self.proposal_count
+    }
+
+    pub(crate) fn vote_governance_proposal(&mut self, voter_id: &AccountId, proposal_id: u64, support: bool) {
+        let mut proposal = self.proposals.get(&proposal_id).expect("Proposal not found");
+        if proposal.status != ProposalStatus::Voting || env::block_timestamp() >= proposal.voting_ends_at.0 {
+            panic!("Voting is finished");
+        }
+        let power = self.governance_power(voter_id);
+        if power == 0 {
+            panic!("No voting power");
+        }
+        if !self.proposal_votes.insert(&(proposal_id, voter_id.to_string())) {
+            panic!("Already voted");
+        }
+
+        if support {
+            proposal.votes_for = U128(proposal.votes_for.0 + power);
+        } else {
+            proposal.votes_against = U128(proposal.votes_against.0 + power);
+        }
+        self.proposals.insert(&proposal_id, &proposal);
+        self.governance_extend_lock(voter_id, proposal.voting_ends_at.0);
+    }
+
+    // Reject failed proposal or check that approved one can be applied
+    pub(crate) fn finalize_governance_proposal(&mut self, proposal_id: u64) -> bool {
+        let mut proposal = self.proposals.get(&proposal_id).expect("Proposal not found");
+        if proposal.status != ProposalStatus::Voting || env::block_timestamp() < proposal.voting_ends_at.0 {
+            panic!("Proposal can't be executed");
+        }
+        proposal.quorum = self.governance_quorum().into();
+        if !proposal.approved() {
+            proposal.status = ProposalStatus::Rejected;
+            self.proposals.insert(&proposal_id, &proposal);
+            return false;
+        }
+        self.proposals.insert(&proposal_id, &proposal);
+        if env::block_timestamp() < proposal.executable_at.0 {
+            panic!("Proposal is in timelock");
+        }
+        true
+    }
+
+    pub(crate) fn apply_governance_proposal(&mut self, proposal_id: u64) {
+        let mut proposal = self.proposals.get(&proposal_id).expect("Proposal not found");
+        if proposal.status != ProposalStatus::Voting || !proposal.approved() {
+            panic!("Proposal can't be executed");
+        }
+
+        match proposal.change.clone() {
+            GovernanceChange::DropRates { mine_type, rates } => self.update_drop_rates(mine_type, rates),
+            GovernanceChange::MinePrice { mine_type, price } => {
+                self.assert_governance_change_valid(&proposal.change);
+                self.mine_prices.insert(&mine_type, &price.0);
+            }
+            GovernanceChange::MarketFee { fee_bps } => self.market_fee_bps = fee_bps,
+            GovernanceChange::KillReward { reward } => self.kill_reward = reward.0,
+        }
+
+        proposal.status = ProposalStatus::Executed;
+        self.proposals.insert(&proposal_id, &proposal);
+    }
+
+    pub(crate) fn proposals_page(&self, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, Proposal> {
+        self.proposals.iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(GOVERNANCE_PROPOSALS_DEFAULT_LIMIT) as usize)
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    const MNL: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn set_context_at(timestamp: u64) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(timestamp);
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+    }
+
+    #[test]
+    fn proposal_executes_after_timelock() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 1_000 * MNL);
+        contract.governance_lock(&accounts(3).into(), 500 * MNL);
+
+        let proposal_id = contract.create_governance_proposal(
+            &accounts(2).into(), "Cheaper kills".to_string(), GovernanceChange::KillReward { reward: U128(MNL / 20) },
+        );
+        contract.vote_governance_proposal(&accounts(2).into(), proposal_id, true);
+        contract.vote_governance_proposal(&accounts(3).into(), proposal_id, false);
+
+        let config = GovernanceConfig::default();
+        set_context_at(10 * DAY + config.voting_period.0 + config.timelock.0);
+        assert!(contract.finalize_governance_proposal(proposal_id));
+        contract.apply_governance_proposal(proposal_id);
+
+        assert_eq!(contract.kill_reward, MNL / 20);
+        assert_eq!(contract.proposals.get(&proposal_id).unwrap().status, ProposalStatus::Executed);
+    }
+
+    #[test]
+    fn market_fee_proposal_sets_fee() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 1_000 * MNL);
+
+        let proposal_id = contract.create_governance_proposal(
+            &accounts(2).into(), "Market fee".to_string(), GovernanceChange::MarketFee { fee_bps: 250 },
+        );
+        contract.vote_governance_proposal(&accounts(2).into(), proposal_id, true);
+
+        let config = GovernanceConfig::default();
+        set_context_at(10 * DAY + config.voting_period.0 + config.timelock.0);
+        assert!(contract.finalize_governance_proposal(proposal_id));
+        contract.apply_governance_proposal(proposal_id);
+
+        assert_eq!(contract.get_market_fee(), 250);
+    }
+
+    #[test]
+    #[should_panic(expected = "Fee can't be more than 100%")]
+    fn market_fee_over_price_rejected() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 1_000 * MNL);
+        contract.create_governance_proposal(
+            &accounts(2).into(), "Market fee".to_string(), GovernanceChange::MarketFee { fee_bps: 10_001 },
+        );
+    }
+
+    #[test]
+    fn proposal_without_quorum_is_rejected() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 100 * MNL);
+        contract.governance_lock(&accounts(3).into(), 1_000 * MNL);
+
+        let proposal_id = contract.create_governance_proposal(
+            &accounts(2).into(), "No reward".to_string(), GovernanceChange::KillReward { reward: U128(0) },
+        );
+        contract.vote_governance_proposal(&accounts(2).into(), proposal_id, true);
+
+        set_context_at(20 * DAY);
+        assert!(!contract.finalize_governance_proposal(proposal_id));
+        assert_eq!(contract.proposals.get(&proposal_id).unwrap().status, ProposalStatus::Rejected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tokens are locked until the end of voting")]
+    fn voter_tokens_locked_during_voting() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 100 * MNL);
+        let proposal_id = contract.create_governance_proposal(
+            &accounts(2).into(), "Reward".to_string(), GovernanceChange::KillReward { reward: U128(MNL) },
+        );
+        contract.vote_governance_proposal(&accounts(2).into(), proposal_id, true);
+        contract.governance_unlock_tokens(&accounts(2).into(), MNL);
+    }
+
+    #[test]
+    fn single_locker_needs_min_quorum() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 100 * MNL);
+        let proposal_id = contract.create_governance_proposal(
+            &accounts(2).into(), "Reward".to_string(), GovernanceChange::KillReward { reward: U128(MNL) },
+        );
+        contract.vote_governance_proposal(&accounts(2).into(), proposal_id, true);
+
+        set_context_at(20 * DAY);
+        assert!(!contract.finalize_governance_proposal(proposal_id));
+        assert_eq!(contract.proposals.get(&proposal_id).unwrap().quorum.0, 1_000 * MNL);
+    }
+
+    #[test]
+    fn failed_unlock_restores_lock() {
+        let mut contract = setup_contract();
+        contract.governance_lock(&accounts(2).into(), 100 * MNL);
+
+        let mut builder = VMContextBuilder::new();
+        builder.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![PromiseResult::Failed]);
+        contract.governance_unlock_tokens(&accounts(2).into(), 40 * MNL);
+        assert_eq!(contract.governance_power(&accounts(2).into()), 60 * MNL);
+
+        assert!(!contract.on_governance_unlocked(accounts(2).into(), U128(40 * MNL)));
+        assert_eq!(contract.governance_power(&accounts(2).into()), 100 * MNL);
+        assert_eq!(contract.governance_locked_total, 100 * MNL);
+    }
+}
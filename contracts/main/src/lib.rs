@@ -1,10 +1,11 @@
 use std::collections::HashMap;
-use std::convert::TryFrom;
 use std::fmt;
 
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::{
-    AccountId, assert_one_yocto, Balance, BorshStorageKey, env, Gas, near_bindgen, Promise, serde_json::json, setup_alloc,
+    AccountId, assert_one_yocto, Balance, BorshStorageKey, env, Gas, near_bindgen, Promise, PromiseOrValue, serde_json::json,
+    setup_alloc,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap};
@@ -13,16 +14,54 @@ use near_sdk::json_types::ValidAccountId;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json::Value as JsonValue;
 
-pub use crate::collection::Collection;
+pub use crate::claim::StoneClaim;
+pub use crate::collection::{Collection, CollectionDropOdds, CollectionSeasons, JsonCollection};
+pub use crate::drop_rates::DropRates;
+use crate::drop_rates::DROP_RATES_TOTAL;
+use crate::events::*;
+use crate::random::RandomStream;
 pub use crate::mine::{Mine, MineType};
-use crate::mine::TotalMineResponse;
+use crate::mine::{MINE_MINT_DEPOSIT, TotalMineResponse};
 pub use crate::stone::{CardRarity, Stone};
+pub use crate::supply::{StoneSupply, SupplyScope};
+pub use crate::staking::{Staker, StakingPool};
+pub use crate::fusion::FusionRecipe;
+pub use crate::reroll::StoneAttribute;
+pub use crate::completion::CollectionProgress;
+pub use crate::monster::Monster;
+pub use crate::equipment::MonsterStats;
+pub use crate::battle::{BattleResult, BattleStage};
+pub use crate::arena::{ArenaChallenge, ArenaResult};
+pub use crate::tournament::{Tournament, TournamentEntry, TournamentMatch, TournamentStatus};
+pub use crate::treasury::{MnlReserve, MnlTreasury};
+pub use crate::governance::{GovernanceChange, GovernanceConfig, GovernanceVoter, Proposal, ProposalStatus};
+use crate::staking::STAKE_LOCK_DEPOSIT;
+use crate::stone::{STONE_DENSITY_RANGE, STONE_DURABILITY_RANGE, STONE_HARDNESS_RANGE};
 
 mod mine;
 mod stone;
 mod collection;
 mod ft;
 mod utils;
+mod drop_rates;
+mod events;
+mod random;
+mod claim;
+mod supply;
+mod staking;
+mod fusion;
+mod reroll;
+mod completion;
+mod monster;
+mod battle;
+mod arena;
+mod tournament;
+mod dismantle;
+mod equipment;
+mod governance;
+mod treasury;
+mod shop;
+mod market;
 
 
 setup_alloc!();
@@ -41,6 +80,34 @@ pub enum StorageKeys {
     UserStoneByCollection,
     UserStoneByCollectionInner { account_hash: AccountId },
     Market,
+    DropRates,
+    StoneClaims,
+    StoneSupplyCaps,
+    StoneSupplyMinted,
+    StoneSupplyBurned,
+    Stakers,
+    StakedStones,
+    FusionRecipes,
+    StoneRerolls,
+    CollectionRewardsClaimed,
+    Monsters,
+    UserMonsters,
+    BattleStages,
+    MonsterBattles,
+    MonsterRatings,
+    ArenaChallenges,
+    Tournaments,
+    EquippedStones,
+    MinePrices,
+    GovernanceLocks,
+    GovernanceUnlockAt,
+    Proposals,
+    ProposalVotes,
+    FailedPayouts,
+    MineMnlPrices,
+    FtStoragePending,
+    MineMintDeposits,
+    FailedTransfers,
 }
 
 #[near_bindgen]
@@ -51,6 +118,12 @@ pub struct Contract {
     contract_nft_stone: AccountId,
     contract_nft_mine: AccountId,
     ft_storage_accounts: LookupSet<AccountId>,
+    ft_storage_pending: LookupSet<AccountId>,
+    mnl_reserve: MnlReserve,
+    failed_payouts: LookupMap<AccountId, Balance>,
+    failed_payouts_total: Balance,
+    failed_transfers: LookupMap<AccountId, Balance>,
+    failed_transfers_total: Balance,
 
     mines: LookupMap<TokenId, Mine>,
     user_mines: LookupMap<AccountId, Vec<TokenId>>,
@@ -59,14 +132,66 @@ pub struct Contract {
     stones: LookupMap<TokenId, Stone>,
     stone_minted_count: u32,
     stone_killed_count: u32,
+    stone_supply_caps: LookupMap<SupplyScope, u32>,
+    stone_supply_minted: LookupMap<SupplyScope, u32>,
+    stone_supply_burned: LookupMap<SupplyScope, u32>,
 
     user_stones: LookupMap<AccountId, Vec<TokenId>>,
     user_stone_by_rarity: LookupMap<AccountId, LookupMap<CardRarity, Vec<TokenId>>>,
     user_stone_by_collection: LookupMap<AccountId, LookupMap<u32, Vec<TokenId>>>,
 
     collections: UnorderedMap<u32, Collection>,
+    collection_id_counter: u32,
 
     market: UnorderedMap<TokenId, AccountId>,
+
+    drop_rates: UnorderedMap<MineType, DropRates>,
+    random_nonce: u64,
+
+    stone_claims: LookupMap<TokenId, StoneClaim>,
+    stone_claim_reveal_required: bool,
+
+    staking_pool: StakingPool,
+    stakers: LookupMap<AccountId, Staker>,
+    staked_stones: LookupSet<TokenId>,
+
+    fusion_recipes: UnorderedMap<CardRarity, FusionRecipe>,
+
+    stone_reroll_base_cost: u128,
+    stone_rerolls: LookupMap<TokenId, u32>,
+
+    collection_completion_reward: u128,
+    collection_rewards_claimed: LookupMap<AccountId, Vec<u32>>,
+
+    monsters: LookupMap<TokenId, Monster>,
+    user_monsters: LookupMap<AccountId, Vec<TokenId>>,
+    monster_minted_count: u32,
+    battle_stages: UnorderedMap<u32, BattleStage>,
+    monster_battles: LookupMap<TokenId, Vec<BattleResult>>,
+    monster_dismantle_fee: u128,
+    // Stone id => monster id
+    equipped_stones: LookupMap<TokenId, TokenId>,
+
+    monster_ratings: LookupMap<TokenId, u32>,
+    arena_challenges: UnorderedMap<u64, ArenaChallenge>,
+    arena_challenge_count: u64,
+    arena_fee_bps: u32,
+    tournaments: UnorderedMap<u64, Tournament>,
+    tournament_count: u64,
+
+    mine_prices: LookupMap<MineType, u128>,
+    mine_mnl_prices: LookupMap<MineType, u128>,
+    mine_mint_deposits: LookupMap<AccountId, Balance>,
+    claim_speed_up_cost: u128,
+    kill_reward: u128,
+    market_fee_bps: u32,
+    governance_config: GovernanceConfig,
+    governance_locks: LookupMap<AccountId, u128>,
+    governance_locked_total: u128,
+    governance_unlock_at: LookupMap<AccountId, u64>,
+    proposals: UnorderedMap<u64, Proposal>,
+    proposal_count: u64,
+    proposal_votes: LookupSet<(u64, AccountId)>,
 }
 
 impl Default for Contract {
@@ -76,12 +201,39 @@ impl Default for Contract {
         mine_counter.insert(&MineType::Medium, &0);
         mine_counter.insert(&MineType::Large, &0);
 
+        let mut drop_rates = UnorderedMap::new(StorageKeys::DropRates);
+        drop_rates.insert(&MineType::Small, &DropRates::default_for(&MineType::Small));
+        drop_rates.insert(&MineType::Medium, &DropRates::default_for(&MineType::Medium));
+        drop_rates.insert(&MineType::Large, &DropRates::default_for(&MineType::Large));
+
+        let mut mine_mnl_prices = LookupMap::new(StorageKeys::MineMnlPrices);
+        mine_mnl_prices.insert(&MineType::Medium, &50_000_000_000_000_000_000_000_000); // 50 MNL
+        mine_mnl_prices.insert(&MineType::Large, &90_000_000_000_000_000_000_000_000); // 90 MNL
+
+        let mut fusion_recipes = UnorderedMap::new(StorageKeys::FusionRecipes);
+        for card_rarity in CardRarity::all() {
+            if let Some(recipe) = FusionRecipe::default_for(&card_rarity) {
+                fusion_recipes.insert(&card_rarity, &recipe);
+            }
+        }
+
+        let mut battle_stages = UnorderedMap::new(StorageKeys::BattleStages);
+        for stage in 1..=5 {
+            battle_stages.insert(&stage, &BattleStage::default_for(stage));
+        }
+
         Self {
             owner_id: env::predecessor_account_id(),
             contract_ft: format!("ft.{}", env::current_account_id()),
             contract_nft_stone: format!("nft-stone.{}", env::current_account_id()),
             contract_nft_mine: format!("nft-mine.{}", env::current_account_id()),
             ft_storage_accounts: LookupSet::new(StorageKeys::FtStorageAccounts),
+            ft_storage_pending: LookupSet::new(StorageKeys::FtStoragePending),
+            mnl_reserve: MnlReserve::default(),
+            failed_payouts: LookupMap::new(StorageKeys::FailedPayouts),
+            failed_payouts_total: 0,
+            failed_transfers: LookupMap::new(StorageKeys::FailedTransfers),
+            failed_transfers_total: 0,
 
             mines: LookupMap::new(StorageKeys::Mines),
             user_mines: LookupMap::new(StorageKeys::UserMines),
@@ -93,10 +245,61 @@ impl Default for Contract {
             user_stone_by_collection: LookupMap::new(StorageKeys::UserStoneByCollection),
             stone_minted_count: 0,
             stone_killed_count: 0,
+            stone_supply_caps: LookupMap::new(StorageKeys::StoneSupplyCaps),
+            stone_supply_minted: LookupMap::new(StorageKeys::StoneSupplyMinted),
+            stone_supply_burned: LookupMap::new(StorageKeys::StoneSupplyBurned),
 
             collections: UnorderedMap::new(StorageKeys::Collections),
+            collection_id_counter: 0,
 
             market: UnorderedMap::new(StorageKeys::Market),
+
+            drop_rates,
+            random_nonce: 0,
+
+            stone_claims: LookupMap::new(StorageKeys::StoneClaims),
+            stone_claim_reveal_required: false,
+
+            staking_pool: StakingPool::new(10_000_000_000_000_000_000_000), // 0.01 MNL per second
+            stakers: LookupMap::new(StorageKeys::Stakers),
+            staked_stones: LookupSet::new(StorageKeys::StakedStones),
+
+            fusion_recipes,
+
+            stone_reroll_base_cost: 1_000_000_000_000_000_000_000_000, // 1 MNL
+            stone_rerolls: LookupMap::new(StorageKeys::StoneRerolls),
+
+            collection_completion_reward: 10_000_000_000_000_000_000_000_000, // 10 MNL
+            collection_rewards_claimed: LookupMap::new(StorageKeys::CollectionRewardsClaimed),
+
+            monsters: LookupMap::new(StorageKeys::Monsters),
+            user_monsters: LookupMap::new(StorageKeys::UserMonsters),
+            monster_minted_count: 0,
+            battle_stages,
+            monster_battles: LookupMap::new(StorageKeys::MonsterBattles),
+            monster_dismantle_fee: 5_000_000_000_000_000_000_000_000, // 5 MNL
+            equipped_stones: LookupMap::new(StorageKeys::EquippedStones),
+
+            monster_ratings: LookupMap::new(StorageKeys::MonsterRatings),
+            arena_challenges: UnorderedMap::new(StorageKeys::ArenaChallenges),
+            arena_challenge_count: 0,
+            arena_fee_bps: 500, // 5%
+            tournaments: UnorderedMap::new(StorageKeys::Tournaments),
+            tournament_count: 0,
+
+            mine_prices: LookupMap::new(StorageKeys::MinePrices),
+            mine_mnl_prices,
+            mine_mint_deposits: LookupMap::new(StorageKeys::MineMintDeposits),
+            claim_speed_up_cost: 1_000_000_000_000_000_000_000_000, // 1 MNL
+            kill_reward: 100_000_000_000_000_000_000_000, // 0.1 MNL
+            market_fee_bps: 0,
+            governance_config: GovernanceConfig::default(),
+            governance_locks: LookupMap::new(StorageKeys::GovernanceLocks),
+            governance_locked_total: 0,
+            governance_unlock_at: LookupMap::new(StorageKeys::GovernanceUnlockAt),
+            proposals: UnorderedMap::new(StorageKeys::Proposals),
+            proposal_count: 0,
+            proposal_votes: LookupSet::new(StorageKeys::ProposalVotes),
         }
     }
 }
@@ -109,7 +312,7 @@ impl Contract {
     // Mint new Mine
     #[payable]
     pub fn mint_mine_nft(&mut self) -> Mine {
-        let mint_deposit: Balance = self.to_yocto("0.01");
+        let mint_deposit: Balance = self.to_yocto(MINE_MINT_DEPOSIT);
         let mint_gas: Gas = self.to_tera(25);
 
         if env::attached_deposit() < mint_deposit {
@@ -117,18 +320,7 @@ impl Contract {
         }
 
         let (mine_metadata, mine) = self.mine_metadata();
-
-        // Add Token Storage
-        if !self.ft_storage_accounts.contains(&env::predecessor_account_id()) {
-            self.add_token_storage(&env::predecessor_account_id());
-        }
-
-        Promise::new(self.contract_nft_mine.clone()).function_call(
-            b"nft_mint".to_vec(),
-            json!(mine_metadata).to_string().as_bytes().to_vec(),
-            mint_deposit,
-            mint_gas,
-        );
+        self.mint_mine_token(&env::predecessor_account_id(), mine_metadata, mint_deposit, mint_gas);
 
         mine
     }
@@ -159,7 +351,7 @@ impl Contract {
     pub fn transfer_mine(&mut self, token_id: TokenId, recipient_id: AccountId) {
         assert_one_yocto();
         let owner_id = env::predecessor_account_id();
-        let mut owner_mines = self.user_mines.get(&owner_id).unwrap_or(vec![]);
+        let owner_mines = self.user_mines.get(&owner_id).unwrap_or(vec![]);
         if !owner_mines.contains(&token_id) {
             panic!("You don't have this Mine");
         }
@@ -167,43 +359,61 @@ impl Contract {
             panic!("You can't send to yourself");
         }
 
-        // Add for recipient
-        let mut recipient_mines = self.user_mines.get(&recipient_id).unwrap_or(vec![]);
-        recipient_mines.push(token_id.to_string());
-        self.user_mines.insert(&recipient_id, &recipient_mines);
-
-        // Remove from sender
-        let index = owner_mines.iter().position(|id| &token_id == id).unwrap();
-        owner_mines.remove(index);
-        self.user_mines.insert(&owner_id, &owner_mines);
-
-        // Send Promise to transfer NFT
-        let deposit: Balance = 1;
-        let gas: Gas = self.to_tera(20);
-        Promise::new(self.contract_nft_mine.clone()).function_call(
-            b"nft_transfer_extended".to_vec(),
-            json!({
-                "sender_id": owner_id.to_string(),
-                "receiver_id": recipient_id.to_string(),
-                "token_id": token_id,
-            }).to_string().as_bytes().to_vec(),
-            deposit,
-            gas,
-        );
-
-        // Add token storage
-        if !self.ft_storage_accounts.contains(&recipient_id.to_string()) {
-            self.add_token_storage(&recipient_id.to_string());
-        }
+        self.move_mine_token(&owner_id, &recipient_id, token_id);
     }
 
     #[payable]
-    pub fn transfer_stone(&mut self, _token_id: TokenId, _recipient_id: AccountId) {
+    pub fn transfer_stone(&mut self, token_id: TokenId, _recipient_id: AccountId) {
         assert_one_yocto();
+        self.assert_stone_unlocked(&token_id);
         // TODO: Transfer
     }
 
 
+    // Mine prices in MNL, mines are bought with ft_transfer_call
+    pub fn get_mine_mnl_prices(&self) -> HashMap<String, U128> {
+        MineType::all().iter()
+            .filter_map(|mine_type| self.mine_mnl_price(mine_type).map(|price| (mine_type.to_string(), U128(price))))
+            .collect()
+    }
+
+    // Deposit NEAR for nft_mint of mines bought with MNL
+    #[payable]
+    pub fn deposit_mine_mint(&mut self) -> U128 {
+        self.add_mine_mint_deposit(&env::predecessor_account_id(), env::attached_deposit()).into()
+    }
+
+    // Withdraw NEAR deposited for nft_mint of mines
+    #[payable]
+    pub fn withdraw_mine_mint_deposit(&mut self) -> U128 {
+        assert_one_yocto();
+        self.refund_mine_mint_deposit(&env::predecessor_account_id()).into()
+    }
+
+    // NEAR available for nft_mint of mines bought with MNL
+    pub fn get_mine_mint_deposit(&self, account_id: ValidAccountId) -> U128 {
+        self.mine_mint_deposits.get(account_id.as_ref()).unwrap_or(0).into()
+    }
+
+    // Set or remove (None) MNL price of the mine type
+    pub fn set_mine_mnl_price(&mut self, mine_type: MineType, price: Option<U128>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        match price {
+            Some(price) => self.mine_mnl_prices.insert(&mine_type, &price.0),
+            None => self.mine_mnl_prices.remove(&mine_type),
+        };
+    }
+
+    // MNL cost of making mine claimable before the end of cooldown
+    pub fn get_claim_speed_up_cost(&self) -> U128 {
+        self.claim_speed_up_cost.into()
+    }
+
+    pub fn set_claim_speed_up_cost(&mut self, cost: U128) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.claim_speed_up_cost = cost.into();
+    }
+
     // -------------- Stones ---------------
 
     // Get claim time for each mine
@@ -214,45 +424,66 @@ impl Contract {
     // Mint new stone for mine
     #[payable]
     pub fn mint_free_stone_nft(&mut self, mine_id: TokenId) -> Vec<Stone> {
+        if self.stone_claim_reveal_required {
+            panic!("Use commit_stone_claim and reveal_stone_claim to claim stones");
+        }
+
         let mint_count = self.mine_claim_free_stone_count(env::predecessor_account_id(), mine_id.clone());
         if mint_count == 0 {
             panic!("You can't claim stones from this Mine");
         }
 
-        let mint_gas: Gas = 20 + (mint_count * 8) as u64;
-        let min_deposit: Balance;
-
-        match mint_count {
-            1 => {
-                min_deposit = self.to_yocto("0.01");
-            }
-            4 => {
-                min_deposit = self.to_yocto("0.03");
-            }
-            8 => {
-                min_deposit = self.to_yocto("0.06");
-            }
-            _ => panic!("Wrong mint_count")
-        }
-
+        let min_deposit: Balance = self.stone_mint_deposit(mint_count);
         if env::attached_deposit() < min_deposit {
             panic!("Attach mint deposit!");
         }
 
-        let (stones_metadata, result) = self.stones_metadata(mint_count, mine_id.clone());
+        let (stones_metadata, result) = self.stones_metadata(mint_count, mine_id);
+        self.mint_stones_promise(stones_metadata, min_deposit);
 
-        Promise::new(self.contract_nft_stone.to_string()).function_call(
-            b"mint_multiple_stones".to_vec(),
-            json!({
-                "stones_metadata": stones_metadata
-            }).to_string().as_bytes().to_vec(),
-            min_deposit,
-            self.to_tera(mint_gas),
-        );
+        result
+    }
+
+    // Commit stone claim for mine, stones are minted in reveal_stone_claim
+    #[payable]
+    pub fn commit_stone_claim(&mut self, mine_id: TokenId) -> StoneClaim {
+        self.commit_claim(mine_id)
+    }
+
+    // Reveal committed claim and mint stones, should be called in a later block
+    pub fn reveal_stone_claim(&mut self, mine_id: TokenId) -> Vec<Stone> {
+        let (stones_metadata, result, claim) = self.reveal_claim(mine_id);
+        self.mint_stones_promise(stones_metadata, claim.deposit);
+
+        result
+    }
+
+    // Get committed stone claim for mine
+    pub fn get_stone_claim(&self, mine_id: TokenId) -> Option<StoneClaim> {
+        self.stone_claims.get(&mine_id)
+    }
 
+    // Require commit-reveal for all stone claims
+    pub fn set_stone_claim_reveal_required(&mut self, required: bool) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.stone_claim_reveal_required = required;
+    }
+
+    // Get stone rarity odds (percentages) for each mine type
+    pub fn get_drop_rates(&self) -> HashMap<String, HashMap<String, f64>> {
+        let mut result = HashMap::new();
+        for (mine_type, rates) in self.drop_rates.iter() {
+            result.insert(mine_type.to_string(), rates.to_percentages());
+        }
         result
     }
 
+    // Update stone rarity odds for mine type
+    pub fn set_drop_rates(&mut self, mine_type: MineType, rates: DropRates) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_drop_rates(mine_type, rates);
+    }
+
     // Get total minted stones count
     pub fn minted_stones_count(self) -> u32 {
         self.stone_minted_count
@@ -263,6 +494,40 @@ impl Contract {
         self.stone_killed_count
     }
 
+    // Set max minted stones for rarity (None - unlimited)
+    pub fn set_rarity_supply_cap(&mut self, card_rarity: CardRarity, cap: Option<u32>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.set_supply_cap(SupplyScope::Rarity(card_rarity), cap);
+    }
+
+    // Set max minted stones for collection (None - unlimited)
+    pub fn set_collection_supply_cap(&mut self, collection_id: u32, cap: Option<u32>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.set_supply_cap(SupplyScope::Collection(collection_id), cap);
+    }
+
+    // Set max minted stones for rarity in collection (None - unlimited)
+    pub fn set_collection_rarity_supply_cap(&mut self, collection_id: u32, card_rarity: CardRarity, cap: Option<u32>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.set_supply_cap(SupplyScope::CollectionRarity(collection_id, card_rarity), cap);
+    }
+
+    // Get stones supply for each rarity, for one collection if collection_id is provided
+    pub fn stone_supply(&self, collection_id: Option<u32>) -> HashMap<String, StoneSupply> {
+        let mut result = HashMap::new();
+        for card_rarity in CardRarity::all() {
+            let scope = match collection_id {
+                Some(collection_id) => SupplyScope::CollectionRarity(collection_id, card_rarity.clone()),
+                None => SupplyScope::Rarity(card_rarity.clone()),
+            };
+            result.insert(card_rarity.to_string(), self.get_stone_supply(&scope));
+        }
+        if let Some(collection_id) = collection_id {
+            result.insert("Total".to_string(), self.get_stone_supply(&SupplyScope::Collection(collection_id)));
+        }
+        result
+    }
+
     // Kill stone
     #[payable]
     pub fn stone_kill(&mut self, stone_id: TokenId) -> String {
@@ -272,6 +537,51 @@ impl Contract {
         self.stone_remove_token_transfer(stone)
     }
 
+    // Fuse stones of one rarity into a stone of the next rarity
+    #[payable]
+    pub fn fuse_stones(&mut self, stone_ids: Vec<TokenId>) -> Stone {
+        assert_one_yocto();
+        let (stone, _) = self.fuse(&env::predecessor_account_id(), stone_ids, 0);
+        stone
+    }
+
+    // Revert fusion if fused stone mint failed, returns unused MNL fee
+    #[private]
+    pub fn on_stones_fused(
+        &mut self,
+        owner_id: AccountId,
+        new_stone: Stone,
+        input_stones: Vec<Stone>,
+        fee_paid: U128,
+        fee: U128,
+    ) -> U128 {
+        self.resolve_fusion(owner_id, new_stone, input_stones, fee_paid.into(), fee.into()).into()
+    }
+
+    // Get fusion recipes for each input rarity
+    pub fn get_fusion_recipes(&self) -> HashMap<String, FusionRecipe> {
+        self.fusion_recipes.iter()
+            .map(|(card_rarity, recipe)| (card_rarity.to_string(), recipe))
+            .collect()
+    }
+
+    // Set or remove (None) fusion recipe for input rarity
+    pub fn set_fusion_recipe(&mut self, card_rarity: CardRarity, recipe: Option<FusionRecipe>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_fusion_recipe(card_rarity, recipe);
+    }
+
+    // MNL cost of the next attribute reroll, paid with ft_transfer_call
+    pub fn get_stone_reroll_cost(&self, stone_id: TokenId) -> U128 {
+        self.stone_reroll_cost(&stone_id).into()
+    }
+
+    // Set cost of the first reroll, it doubles with each reroll of the same stone
+    pub fn set_stone_reroll_base_cost(&mut self, cost: U128) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.stone_reroll_base_cost = cost.into();
+    }
+
     // User stones with pagination
     pub fn user_stones(&self, account_id: AccountId, page_num: U64, page_limit: U64, filter_rarity: Option<CardRarity>, filter_collection: Option<u32>) -> (U64, Vec<Stone>) {
         let page_num = page_num.into();
@@ -292,12 +602,105 @@ impl Contract {
             panic!("All fields is required");
         }
 
-        self.add_new_collection(title, image, stone_images)
+        self.add_new_collection(title, image, stone_images);
+    }
+
+    // Add new collection which drops only in its season
+    pub fn schedule_collection(
+        &mut self,
+        title: String,
+        image: String,
+        stone_images: Vec<String>,
+        starts_at: U64,
+        ends_at: Option<U64>,
+    ) -> u32 {
+        self.assert_contract_owner(self.owner_id.to_string());
+        if title.is_empty() || image.is_empty() || stone_images.is_empty() {
+            panic!("All fields is required");
+        }
+
+        let collection_id = self.add_new_collection(title, image, stone_images);
+        self.update_collection_season(collection_id, Some(starts_at.into()), ends_at.map(|ends_at| ends_at.into()));
+        collection_id
+    }
+
+    // Set collection season window (nanoseconds), None for no limit
+    pub fn set_collection_season(&mut self, collection_id: u32, starts_at: Option<U64>, ends_at: Option<U64>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_collection_season(
+            collection_id,
+            starts_at.map(|starts_at| starts_at.into()),
+            ends_at.map(|ends_at| ends_at.into()),
+        );
+    }
+
+    // Get current, upcoming and past seasonal collections
+    pub fn get_collection_seasons(&self) -> CollectionSeasons {
+        self.collection_seasons()
+    }
+
+    // Update collection fields, stone images can be replaced or appended
+    pub fn update_collection(
+        &mut self,
+        collection_id: u32,
+        title: Option<String>,
+        image: Option<String>,
+        stone_images: Option<Vec<String>>,
+    ) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_existing_collection(collection_id, title, image, stone_images);
+    }
+
+    // Set collection description, artist, royalties (basis points) and art for each rarity
+    pub fn set_collection_details(
+        &mut self,
+        collection_id: u32,
+        description: Option<String>,
+        artist_id: Option<ValidAccountId>,
+        royalty: HashMap<AccountId, u32>,
+        rarity_stone_images: HashMap<String, Vec<String>>,
+    ) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_collection_details(
+            collection_id,
+            description,
+            artist_id.map(|artist_id| artist_id.into()),
+            royalty,
+            rarity_stone_images,
+        );
+    }
+
+    // Set collection drop weight, allowed mine types (None for all) and stone image weights
+    pub fn set_collection_drop_weights(
+        &mut self,
+        collection_id: u32,
+        weight: u32,
+        mine_types: Option<Vec<MineType>>,
+        stone_image_weights: Option<Vec<u32>>,
+    ) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_collection_drop_weights(collection_id, weight, mine_types, stone_image_weights);
+    }
+
+    // Get drop odds (percent) of each collection and its stone images for mine type
+    pub fn get_collection_drop_odds(&self, mine_type: MineType) -> HashMap<u32, CollectionDropOdds> {
+        self.collection_drop_odds(&mine_type)
+    }
+
+    // Add collection to (true) or remove from (false) the drop pool
+    pub fn set_collection_active(&mut self, collection_id: u32, active: bool) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_collection_active(collection_id, active);
     }
 
-    // Get all Collections
-    pub fn get_collections(&self) -> HashMap<u32, Collection> {
-        self.collections.iter().collect()
+    // Get Collections with pagination
+    pub fn get_collections(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<JsonCollection> {
+        self.collections_by_ids(self.collection_ids_page(from_index, limit))
+    }
+
+    // Get Collections by ids, missing ids are skipped
+    pub fn get_collections_by_ids(&self, collection_ids: Vec<u32>) -> Vec<JsonCollection> {
+        self.collections_by_ids(collection_ids)
     }
 
     // Get one Collection
@@ -305,9 +708,373 @@ impl Contract {
         self.collections.get(&collection_id).unwrap()
     }
 
-    // Get user stones count for each collection
-    pub fn user_collection_counts(&self, account_id: AccountId) -> HashMap<u32, u32> {
-        self.get_user_collection_counts(&account_id)
+    // Get user stones count for each collection with pagination
+    pub fn user_collection_counts(&self, account_id: AccountId, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u32, u32> {
+        self.get_user_collection_counts(&account_id, self.collection_ids_page(from_index, limit))
+    }
+
+    // Get user completion progress for each collection with pagination
+    pub fn get_collections_progress(&self, account_id: AccountId, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u32, CollectionProgress> {
+        self.user_collections_progress(&account_id, self.collection_ids_page(from_index, limit))
+    }
+
+    // Claim MNL bonus and badge NFT for completed collection, attach badge mint deposit
+    #[payable]
+    pub fn claim_collection_completion(&mut self, collection_id: u32) -> U128 {
+        self.claim_collection_reward(&env::predecessor_account_id(), collection_id).into()
+    }
+
+    // Set MNL bonus for collection completion
+    pub fn set_collection_completion_reward(&mut self, reward: U128) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.collection_completion_reward = reward.into();
+    }
+
+    // ------------- Monsters --------------
+
+    // Craft monster from one stone of each collection image, input stones are burned
+    #[payable]
+    pub fn mint_collection(&mut self, stone_ids: Vec<TokenId>, collection_id: u32) -> Monster {
+        if env::attached_deposit() < self.to_yocto("0.01") {
+            panic!("Attach mint deposit!");
+        }
+        self.craft_monster(&env::predecessor_account_id(), stone_ids, collection_id)
+    }
+
+    // Get total minted monsters
+    pub fn minted_monsters_count(&self) -> u32 {
+        self.monster_minted_count
+    }
+
+    pub fn get_monster(&self, monster_id: TokenId) -> Monster {
+        self.monsters.get(&monster_id).expect("Monster not found")
+    }
+
+    pub fn user_monsters(&self, account_id: ValidAccountId) -> Vec<Monster> {
+        let mut user_monster_ids = self.user_monsters.get(&account_id.into()).unwrap_or(vec![]);
+        user_monster_ids.reverse();
+        self.get_monsters_by_id(user_monster_ids)
+    }
+
+    // Monster level and stats with equipment
+    pub fn get_monster_stats(&self, monster_id: TokenId) -> MonsterStats {
+        self.monster_stats(&monster_id)
+    }
+
+    // Equip stone to add its attributes to the monster, attach lock storage deposit
+    #[payable]
+    pub fn equip_monster_stone(&mut self, monster_id: TokenId, stone_id: TokenId) {
+        self.equip_stone(&env::predecessor_account_id(), monster_id, stone_id);
+    }
+
+    #[payable]
+    pub fn unequip_monster_stone(&mut self, monster_id: TokenId, stone_id: TokenId) {
+        assert_one_yocto();
+        self.unequip_stone(&env::predecessor_account_id(), monster_id, stone_id);
+    }
+
+    // Send monster against stage enemy, win pays MNL and XP
+    #[payable]
+    pub fn battle_monster(&mut self, monster_id: TokenId, stage: u32) -> BattleResult {
+        assert_one_yocto();
+        self.monster_battle(&env::predecessor_account_id(), monster_id, stage)
+    }
+
+    // Get last battles of the monster
+    pub fn get_monster_battles(&self, monster_id: TokenId) -> Vec<BattleResult> {
+        self.monster_battles.get(&monster_id).unwrap_or(vec![])
+    }
+
+    pub fn get_battle_stages(&self) -> HashMap<u32, BattleStage> {
+        self.battle_stages.iter().collect()
+    }
+
+    // Set or remove (None) battle stage
+    pub fn set_battle_stage(&mut self, stage: u32, battle_stage: Option<BattleStage>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.update_battle_stage(stage, battle_stage);
+    }
+
+    // Burn monster and get part of its stones back, works only when dismantle fee is zero
+    // (otherwise pay the fee with ft_transfer_call)
+    #[payable]
+    pub fn dismantle_monster(&mut self, monster_id: TokenId) -> Vec<Stone> {
+        assert_one_yocto();
+        let (stones, _) = self.dismantle(&env::predecessor_account_id(), monster_id, 0);
+        stones
+    }
+
+    // Revert dismantle if nft-stone call failed, returns unused MNL fee
+    #[private]
+    pub fn on_monster_dismantled(&mut self, monster: Monster, new_stones: Vec<Stone>, fee_paid: U128, fee: U128) -> U128 {
+        self.resolve_dismantle(monster, new_stones, fee_paid.into(), fee.into()).into()
+    }
+
+    // Set MNL fee for dismantling monster
+    pub fn set_monster_dismantle_fee(&mut self, fee: U128) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.monster_dismantle_fee = fee.into();
+    }
+
+    // --------------- Arena ----------------
+
+    // Monster Elo rating
+    pub fn get_monster_rating(&self, monster_id: TokenId) -> u32 {
+        self.monster_rating(&monster_id)
+    }
+
+    // Get challenges of the account from a page of all challenges
+    pub fn get_arena_challenges(&self, account_id: AccountId, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, ArenaChallenge> {
+        self.arena_challenges_page(&account_id, from_index, limit)
+    }
+
+    // Cancel challenge by challenger or opponent, stake is returned to challenger
+    #[payable]
+    pub fn cancel_arena_challenge(&mut self, challenge_id: u64) {
+        assert_one_yocto();
+        self.arena_cancel(&env::predecessor_account_id(), challenge_id);
+    }
+
+    // Set arena fee in basis points of the pot
+    pub fn set_arena_fee(&mut self, fee_bps: u32) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        if fee_bps > 10_000 {
+            panic!("Fee should not exceed 100%");
+        }
+        self.arena_fee_bps = fee_bps;
+    }
+
+    // Schedule tournament, players join with ft_transfer_call paying entry fee
+    pub fn create_tournament(&mut self, name: String, entry_fee: U128, max_players: u32, prize_shares: Vec<u32>) -> u64 {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.add_tournament(name, entry_fee.into(), max_players, prize_shares)
+    }
+
+    // Close registration and seed bracket by rating
+    pub fn start_tournament(&mut self, tournament_id: u64) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.tournament_start(tournament_id);
+    }
+
+    // Cancel tournament which was not started, entry fees are returned
+    pub fn cancel_tournament(&mut self, tournament_id: u64) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.tournament_cancel(tournament_id);
+    }
+
+    // Resolve current tournament round, prizes are paid after the final
+    #[payable]
+    pub fn advance_tournament(&mut self, tournament_id: u64) -> Vec<TournamentMatch> {
+        assert_one_yocto();
+        self.tournament_advance(&env::predecessor_account_id(), tournament_id)
+    }
+
+    pub fn get_tournament(&self, tournament_id: u64) -> Tournament {
+        self.tournaments.get(&tournament_id).expect("Tournament not found")
+    }
+
+    pub fn get_tournaments(&self, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, Tournament> {
+        self.tournaments_page(from_index, limit)
+    }
+
+    // ------------- Staking --------------
+
+    // Stake stones to earn MNL, attach lock storage deposit for each stone
+    #[payable]
+    pub fn stake_stones(&mut self, stone_ids: Vec<TokenId>) {
+        self.stake(&env::predecessor_account_id(), stone_ids);
+    }
+
+    // Unstake stones, pending rewards stay available for claim
+    #[payable]
+    pub fn unstake_stones(&mut self, stone_ids: Vec<TokenId>) {
+        assert_one_yocto();
+        self.unstake(&env::predecessor_account_id(), stone_ids);
+    }
+
+    // Claim staking rewards
+    #[payable]
+    pub fn claim_staking_rewards(&mut self) -> U128 {
+        assert_one_yocto();
+        self.claim_staking(&env::predecessor_account_id()).into()
+    }
+
+    // Restore claimed rewards if MNL transfer failed
+    #[private]
+    pub fn on_staking_claimed(&mut self, account_id: AccountId, amount: U128) -> bool {
+        self.resolve_staking_claim(&account_id, amount.into())
+    }
+
+    // Get user pending staking rewards
+    pub fn staking_pending_rewards(&self, account_id: AccountId) -> U128 {
+        self.staking_pending(&account_id).into()
+    }
+
+    // Get user staked stones
+    pub fn user_staked_stones(&self, account_id: AccountId) -> Vec<Stone> {
+        let stone_ids = self.stakers.get(&account_id).map(|staker| staker.stone_ids).unwrap_or(vec![]);
+        self.get_stones_by_id(stone_ids)
+    }
+
+    // Get staking pool info
+    pub fn get_staking_pool(&self) -> StakingPool {
+        StakingPool {
+            acc_reward_per_share: self.staking_pool.acc_reward_per_share_at(env::block_timestamp()),
+            ..self.staking_pool.clone()
+        }
+    }
+
+    // Update staking MNL reward per second for all staked stones
+    pub fn set_staking_reward_rate(&mut self, reward_per_second: U128) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.staking_pool.update(env::block_timestamp());
+        self.staking_pool.reward_per_second = reward_per_second.into();
+    }
+
+    // ------------- Governance --------------
+
+    // Voting power (MNL locked with ft_transfer_call) and unlock time
+    pub fn get_governance_voter(&self, account_id: ValidAccountId) -> GovernanceVoter {
+        GovernanceVoter {
+            power: self.governance_power(account_id.as_ref()).into(),
+            unlock_at: self.governance_unlock_at.get(account_id.as_ref()).unwrap_or(0).into(),
+        }
+    }
+
+    // Withdraw locked MNL, not possible while proposals voted by account are in voting
+    #[payable]
+    pub fn governance_unlock(&mut self, amount: U128) {
+        assert_one_yocto();
+        self.governance_unlock_tokens(&env::predecessor_account_id(), amount.into());
+    }
+
+    // Restore lock if unlocked MNL transfer failed
+    #[private]
+    pub fn on_governance_unlocked(&mut self, account_id: AccountId, amount: U128) -> bool {
+        self.resolve_governance_unlock(&account_id, amount.into())
+    }
+
+    // Create proposal to change game parameter, returns proposal id
+    pub fn create_proposal(&mut self, description: String, change: GovernanceChange) -> u64 {
+        self.create_governance_proposal(&env::predecessor_account_id(), description, change)
+    }
+
+    // Vote with all locked MNL
+    pub fn vote_proposal(&mut self, proposal_id: u64, support: bool) {
+        self.vote_governance_proposal(&env::predecessor_account_id(), proposal_id, support);
+    }
+
+    // Reject failed proposal or apply approved one after timelock
+    pub fn execute_proposal(&mut self, proposal_id: u64) {
+        if self.finalize_governance_proposal(proposal_id) {
+            Promise::new(env::current_account_id()).function_call(
+                b"apply_proposal".to_vec(),
+                json!({
+                    "proposal_id": proposal_id,
+                }).to_string().as_bytes().to_vec(),
+                0,
+                self.to_tera(20),
+            );
+        }
+    }
+
+    // Admin entry point for approved proposals
+    #[private]
+    pub fn apply_proposal(&mut self, proposal_id: u64) {
+        self.apply_governance_proposal(proposal_id);
+    }
+
+    pub fn get_proposal(&self, proposal_id: u64) -> Option<Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    // Get proposals with pagination
+    pub fn get_proposals(&self, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, Proposal> {
+        self.proposals_page(from_index, limit)
+    }
+
+    pub fn get_governance_config(&self) -> GovernanceConfig {
+        self.governance_config.clone()
+    }
+
+    pub fn set_governance_config(&mut self, config: GovernanceConfig) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        if config.quorum_bps > 10_000 {
+            panic!("Quorum can't be more than 100%");
+        }
+        self.governance_config = config;
+    }
+
+    // MNL added to stone kill reward for each doubling of stone age, set by governance
+    pub fn get_kill_reward(&self) -> U128 {
+        self.kill_reward.into()
+    }
+
+    // ------------- MNL Token --------------
+
+    // Mark account registered on the token if storage_deposit succeeded
+    #[private]
+    pub fn on_ft_storage_registered(&mut self, account_id: AccountId) -> bool {
+        self.resolve_ft_storage_registered(&account_id)
+    }
+
+    // Register account with minimum deposit from storage_balance_bounds
+    #[private]
+    pub fn on_ft_storage_bounds(&mut self, account_id: AccountId) -> PromiseOrValue<bool> {
+        self.resolve_ft_storage_bounds(&account_id)
+    }
+
+    // Register accounts on the token again, for accounts with wrong registration state
+    pub fn repair_ft_storage(&mut self, account_ids: Vec<ValidAccountId>) {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.repair_token_storage(account_ids.into_iter().map(|account_id| account_id.into()).collect());
+    }
+
+    // Read MNL reserve for game rewards from the token emission caps
+    pub fn sync_mnl_reserve(&mut self) -> Promise {
+        self.assert_contract_owner(self.owner_id.to_string());
+        self.mnl_reserve_sync()
+    }
+
+    // Save MNL reserve, returns current reserve
+    #[private]
+    pub fn on_mnl_reserve_synced(&mut self) -> U128 {
+        self.resolve_mnl_reserve()
+    }
+
+    // Record reward as failed payout if mint failed
+    #[private]
+    pub fn on_mnl_reward_paid(&mut self, account_id: AccountId, amount: U128) -> bool {
+        self.resolve_mnl_reward(&account_id, amount.into())
+    }
+
+    // Record MNL owed to account if transfer failed
+    #[private]
+    pub fn on_mnl_payout_transferred(&mut self, account_id: AccountId, amount: U128) -> bool {
+        self.resolve_mnl_transfer(&account_id, amount.into())
+    }
+
+    // Pay failed rewards and transfers again, returns paid amount
+    #[payable]
+    pub fn retry_failed_payouts(&mut self) -> U128 {
+        assert_one_yocto();
+        self.retry_payouts(&env::predecessor_account_id()).into()
+    }
+
+    // MNL reserve for game rewards and total of failed payouts
+    pub fn get_mnl_treasury(&self) -> MnlTreasury {
+        MnlTreasury {
+            reserve: self.mnl_reserve.available(env::block_timestamp()).into(),
+            failed_payouts: self.failed_payouts_total.into(),
+            failed_transfers: self.failed_transfers_total.into(),
+        }
+    }
+
+    // Rewards and transfers owed to account after failed payouts
+    pub fn get_failed_payout(&self, account_id: ValidAccountId) -> U128 {
+        let rewards = self.failed_payouts.get(account_id.as_ref()).unwrap_or(0);
+        let transfers = self.failed_transfers.get(account_id.as_ref()).unwrap_or(0);
+        (rewards + transfers).into()
     }
 
     // ------------- Market --------------
@@ -358,6 +1125,7 @@ impl Contract {
             if !user_stones.contains(&token_id) {
                 panic!("You don't own this stone");
             }
+            self.assert_stone_unlocked(&token_id);
 
             let mut stone = self.stones.get(&token_id).unwrap();
             self.market.insert(&token_id, &owner_id);
@@ -382,6 +1150,17 @@ impl Contract {
         }
     }
 
+    // Buy Mine from the market, seller gets sale price without market fee
+    #[payable]
+    pub fn buy_mine_from_market(&mut self, token_id: TokenId) {
+        self.buy_market_mine(&env::predecessor_account_id(), token_id, env::attached_deposit());
+    }
+
+    // Market fee in basis points of the sale price, set by governance
+    pub fn get_market_fee(&self) -> u32 {
+        self.market_fee_bps
+    }
+
     // Get stones on the market
     pub fn get_stones_from_market(&self, start: u64, limit: u64) -> Vec<Stone> {
         let token_list = self.get_tokens_from_market(start, limit);
@@ -0,0 +1,91 @@
+use crate::*;
+
+// Market fee can't exceed the sale price
+pub const MARKET_FEE_MAX_BPS: u32 = 10_000;
+
+impl Contract {
+    // Part of the sale price kept by the contract
+    pub(crate) fn market_fee(&self, price: u128) -> u128 {
+        price * self.market_fee_bps as u128 / MARKET_FEE_MAX_BPS as u128
+    }
+
+    // Buy listed mine for NEAR: mine goes to the buyer, seller is paid the price without fee,
+    // rest of the deposit is returned to the buyer
+    pub(crate) fn buy_market_mine(&mut self, buyer_id: &AccountId, token_id: TokenId, deposit: Balance) {
+        let seller_id = self.market.get(&token_id).expect("Mine is not on the market");
+        let mut mine = self.mines.get(&token_id).expect("Mine is not on the market");
+        let price = mine.sale_price.expect("Mine is not on the market");
+        if &seller_id == buyer_id {
+            panic!("You can't buy your own mine");
+        }
+        if deposit < price {
+            panic!("Attach {} yoctoNEAR to buy this mine", price);
+        }
+
+        self.market.remove(&token_id);
+        mine.sale_price = None;
+        self.mines.insert(&token_id, &mine);
+        self.move_mine_token(&seller_id, buyer_id, token_id);
+
+        let seller_amount = price - self.market_fee(price);
+        if seller_amount > 0 {
+            Promise::new(seller_id).transfer(seller_amount);
+        }
+        if deposit > price {
+            Promise::new(buyer_id.to_string()).transfer(deposit - price);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::collections::HashMap;
+
+    use near_sdk::test_utils::accounts;
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    fn list_test_mine(contract: &mut Contract, price: u128) -> TokenId {
+        set_context(2, contract.to_yocto("0.01"));
+        let mine_id = contract.mint_mine_nft().token_id;
+        set_context(2, 1);
+        let mut prices = HashMap::new();
+        prices.insert(mine_id.to_string(), U128(price));
+        contract.publish_mines_on_market(prices);
+        mine_id
+    }
+
+    #[test]
+    fn market_fee_from_price() {
+        let mut contract = setup_contract();
+        assert_eq!(contract.market_fee(1_000), 0);
+        contract.market_fee_bps = 250;
+        assert_eq!(contract.market_fee(1_000), 25);
+    }
+
+    #[test]
+    fn buy_mine_moves_it_to_buyer() {
+        let mut contract = setup_contract();
+        let mine_id = list_test_mine(&mut contract, 100);
+
+        set_context(3, 150);
+        contract.buy_mine_from_market(mine_id.to_string());
+
+        assert!(contract.market.get(&mine_id).is_none());
+        assert_eq!(contract.mines.get(&mine_id).unwrap().sale_price, None);
+        assert!(contract.user_mines(accounts(2)).is_empty());
+        assert_eq!(contract.user_mines(accounts(3))[0].token_id, mine_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach 100 yoctoNEAR to buy this mine")]
+    fn buy_mine_requires_price() {
+        let mut contract = setup_contract();
+        let mine_id = list_test_mine(&mut contract, 100);
+
+        set_context(3, 99);
+        contract.buy_mine_from_market(mine_id);
+    }
+}
@@ -7,7 +7,13 @@ use near_sdk::Timestamp;
 
 use crate::*;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+// Stone claim cooldown of a mine
+pub const MINE_CLAIM_COOLDOWN: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+// NEAR attached to nft_mint of a mine token
+pub const MINE_MINT_DEPOSIT: &str = "0.01";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum MineType {
     Small,
@@ -25,6 +31,12 @@ impl fmt::Display for MineType {
     }
 }
 
+impl MineType {
+    pub fn all() -> Vec<MineType> {
+        vec![MineType::Small, MineType::Medium, MineType::Large]
+    }
+}
+
 impl FromStr for MineType {
     type Err = ParseError;
 
@@ -84,7 +96,12 @@ impl Mine {
         }
     }
 
+    // Mine price set by governance or the default one
     pub fn mine_prices(mine_type: &MineType, contract: &Contract) -> u128 {
+        contract.mine_prices.get(mine_type).unwrap_or_else(|| Mine::default_mine_price(mine_type, contract))
+    }
+
+    pub fn default_mine_price(mine_type: &MineType, contract: &Contract) -> u128 {
         let small_deposit: u128 = contract.to_yocto("0.01");
 
         match mine_type {
@@ -118,6 +135,11 @@ impl Contract {
             panic!("Wrong deposit amount");
         }
 
+        self.create_mine(owner_id, mine_type)
+    }
+
+    // Add new mine of the type to owner, returns nft_mint arguments and the mine
+    pub(crate) fn create_mine(&mut self, owner_id: AccountId, mine_type: MineType) -> (JsonValue, Mine) {
         let media_url: String = Mine::mine_media_hash(&mine_type);
         let mines_limit: u32 = Mine::mine_limits(&mine_type);
         let mines_count: u32 = self.mines_count.get(&mine_type).unwrap() + 1;
@@ -161,6 +183,49 @@ impl Contract {
         (metadata, mine)
     }
 
+    pub(crate) fn mint_mine_token(&mut self, owner_id: &AccountId, mine_metadata: JsonValue, deposit: Balance, gas: Gas) {
+        // Add Token Storage
+        self.ensure_token_storage(owner_id);
+
+        Promise::new(self.contract_nft_mine.clone()).function_call(
+            b"nft_mint".to_vec(),
+            json!(mine_metadata).to_string().as_bytes().to_vec(),
+            deposit,
+            gas,
+        );
+    }
+
+    // Move mine to recipient and transfer NFT
+    pub(crate) fn move_mine_token(&mut self, owner_id: &AccountId, recipient_id: &AccountId, token_id: TokenId) {
+        // Add for recipient
+        let mut recipient_mines = self.user_mines.get(recipient_id).unwrap_or(vec![]);
+        recipient_mines.push(token_id.to_string());
+        self.user_mines.insert(recipient_id, &recipient_mines);
+
+        // Remove from sender
+        let mut owner_mines = self.user_mines.get(owner_id).unwrap_or(vec![]);
+        let index = owner_mines.iter().position(|id| &token_id == id).unwrap();
+        owner_mines.remove(index);
+        self.user_mines.insert(owner_id, &owner_mines);
+
+        // Send Promise to transfer NFT
+        let deposit: Balance = 1;
+        let gas: Gas = self.to_tera(20);
+        Promise::new(self.contract_nft_mine.clone()).function_call(
+            b"nft_transfer_extended".to_vec(),
+            json!({
+                "sender_id": owner_id.to_string(),
+                "receiver_id": recipient_id.to_string(),
+                "token_id": token_id,
+            }).to_string().as_bytes().to_vec(),
+            deposit,
+            gas,
+        );
+
+        // Add token storage
+        self.ensure_token_storage(recipient_id);
+    }
+
     pub(crate) fn check_mint_free_mine(&self, account_id: String) {
         let user_mines = self.user_mines.get(&account_id).unwrap_or(vec![]);
 
@@ -174,7 +239,7 @@ impl Contract {
 
     pub(crate) fn stone_free_claim_time(&self, account_id: AccountId, timestamp: u64) -> HashMap<String, u64> {
         let mut result: HashMap<String, u64> = HashMap::new();
-        let allow_claim_timestamp = timestamp - 1_000_000_000 * 60 * 60 * 24;
+        let allow_claim_timestamp = timestamp - MINE_CLAIM_COOLDOWN;
         let user_mines = self.user_mines.get(&account_id).unwrap().to_vec();
 
         for user_mine in user_mines {
@@ -191,7 +256,7 @@ impl Contract {
     }
 
     pub(crate) fn mine_claim_free_stone_count(&self, account_id: AccountId, mine_id: TokenId) -> u8 {
-        let allow_claim_timestamp = env::block_timestamp() - 1_000_000_000 * 60 * 60 * 24;
+        let allow_claim_timestamp = env::block_timestamp() - MINE_CLAIM_COOLDOWN;
         let user_mines = self.user_mines.get(&account_id).unwrap().to_vec();
 
         for user_mine in user_mines {
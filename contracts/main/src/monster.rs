@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use crate::*;
+
+#[derive(Debug, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Monster {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub collection_id: u32,
+    pub media: String,
+    pub kill_tokens: String,
+    pub mint_date: u64,
+    pub hardness: u8,
+    pub density: u8,
+    pub durability: u8,
+    pub xp: u32,
+    pub last_battle_at: Option<u64>,
+    pub level: u32,
+    // Equipped stones, their attributes are added to the monster
+    pub equipment: Vec<TokenId>,
+    pub bonus_hardness: u32,
+    pub bonus_density: u32,
+    pub bonus_durability: u32,
+}
+
+impl Contract {
+    // Burn one stone for each collection image and mint a monster with summed attributes
+    pub(crate) fn craft_monster(&mut self, owner_id: &AccountId, stone_ids: Vec<TokenId>, collection_id: u32) -> Monster {
+        let collection = self.collections.get(&collection_id).expect("Collection not found");
+        let stones = self.get_stones_by_id(stone_ids.to_vec());
+        if stones.len() != stone_ids.len() {
+            panic!("Stone not found");
+        }
+
+        let collection_indexes: HashSet<u8> = stones.iter().map(|stone| stone.collection_index).collect();
+        if stones.len() != collection.stone_images.len() || collection_indexes.len() != stones.len() {
+            panic!("Provide one stone for each collection image");
+        }
+
+        let mut kill_tokens: u128 = 0;
+        let (mut hardness, mut density, mut durability) = (0u8, 0u8, 0u8);
+        for stone in stones.iter() {
+            if stone.collection_id != collection_id {
+                panic!("All stones should be from the collection");
+            }
+            if self.market.get(&stone.token_id).is_some() {
+                panic!("Remove stone from market before crafting");
+            }
+            // checks owner & locks
+            self.stone_remove_from_user(stone, owner_id);
+
+            hardness = hardness.saturating_add(stone.hardness);
+            density = density.saturating_add(stone.density);
+            durability = durability.saturating_add(stone.durability);
+            kill_tokens += stone.kill_tokens.parse::<u128>().unwrap();
+        }
+
+        self.monster_minted_count += 1;
+        let monster = Monster {
+            token_id: format!("mn-{}-{}", self.monster_minted_count, self.random_u8(0)),
+            owner_id: owner_id.to_string(),
+            collection_id,
+            media: collection.image,
+            kill_tokens: kill_tokens.to_string(),
+            mint_date: env::block_timestamp(),
+            hardness,
+            density,
+            durability,
+            xp: 0,
+            last_battle_at: None,
+            level: 1,
+            equipment: vec![],
+            bonus_hardness: 0,
+            bonus_density: 0,
+            bonus_durability: 0,
+        };
+
+        let mut user_monsters = self.user_monsters.get(owner_id).unwrap_or(vec![]);
+        user_monsters.push(monster.token_id.to_string());
+        self.user_monsters.insert(owner_id, &user_monsters);
+        self.monsters.insert(&monster.token_id, &monster);
+
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"mint_monster".to_vec(),
+            json!({
+                "monster_data": {
+                    "token_id": monster.token_id,
+                    "receiver_id": owner_id,
+                    "token_metadata": {
+                        "title": format!("Monster #{}", self.monster_minted_count),
+                        "media": monster.media,
+                        "copies": 1,
+                        "extra": monster.metadata_extra()
+                    },
+                    "input_stones": stone_ids,
+                }
+            }).to_string().as_bytes().to_vec(),
+            env::attached_deposit(),
+            self.to_tera(20 + 5 * stone_ids.len() as u64),
+        );
+
+        monster
+    }
+
+    pub(crate) fn assert_monster_owner(&self, monster_id: &TokenId, owner_id: &AccountId) -> Monster {
+        let user_monsters = self.user_monsters.get(owner_id).unwrap_or(vec![]);
+        if !user_monsters.contains(monster_id) {
+            panic!("You don't own this monster");
+        }
+        self.monsters.get(monster_id).unwrap()
+    }
+
+    pub(crate) fn get_monsters_by_id(&self, id_list: Vec<TokenId>) -> Vec<Monster> {
+        id_list.into_iter()
+            .flat_map(|token_id| self.monsters.get(&token_id))
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) mod test_utils {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    pub const DAY: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+    pub fn set_context(predecessor: usize, deposit: Balance) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(predecessor))
+            .block_timestamp(10 * DAY)
+            .attached_deposit(deposit);
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+    }
+
+    // Contract owned by accounts(1) with one-image collection
+    pub fn setup_contract() -> Contract {
+        set_context(1, 0);
+        let mut contract = Contract::default();
+        contract.add_collection("Collection 1".to_string(), "image".to_string(), vec!["1-1".to_string()]);
+        contract
+    }
+
+    // Claim stone and craft monster for account
+    pub fn mint_test_monster(contract: &mut Contract, account: usize) -> TokenId {
+        set_context(account, contract.to_yocto("0.01"));
+        let mine_id = contract.mint_mine_nft().token_id;
+        let stone = contract.mint_free_stone_nft(mine_id).pop().unwrap();
+        contract.mint_collection(vec![stone.token_id], 1).token_id
+    }
+
+    // Craft monster from new Common stones (2, 2, 1) for each of first `images` collection images
+    pub fn mint_test_monster_from_images(contract: &mut Contract, account: usize, images: u8) -> TokenId {
+        set_context(account, contract.to_yocto("0.01"));
+        let stone_ids = (0..images).map(|index| {
+            let (_, stone) = contract.create_stone(
+                &accounts(account).into(), index as u32 + 1, 1, CardRarity::Common, (2, 2, 1), index,
+            );
+            stone.token_id
+        }).collect();
+        contract.mint_collection(stone_ids, 1).token_id
+    }
+}
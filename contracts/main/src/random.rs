@@ -0,0 +1,211 @@
+use crate::*;
+
+// Deterministic stream of random numbers.
+// Every block is sha256(base_hash || block_index), where base_hash mixes the block random seed,
+// the account, the mine and a per-call nonce, so values never overlap between stones or calls.
+pub struct RandomStream {
+    base_hash: Vec<u8>,
+    block_index: u64,
+    block: Vec<u8>,
+    position: usize,
+}
+
+impl RandomStream {
+    pub fn new(seed: &[u8], account_id: &str, mine_id: &str, nonce: u64) -> Self {
+        let mut input: Vec<u8> = seed.to_vec();
+        input.extend_from_slice(account_id.as_bytes());
+        input.push(b'|');
+        input.extend_from_slice(mine_id.as_bytes());
+        input.extend_from_slice(&nonce.to_le_bytes());
+
+        Self {
+            base_hash: env::sha256(&input),
+            block_index: 0,
+            block: vec![],
+            position: 0,
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut input = self.base_hash.clone();
+        input.extend_from_slice(&self.block_index.to_le_bytes());
+        self.block = env::sha256(&input);
+        self.block_index += 1;
+        self.position = 0;
+    }
+
+    // Get next random u32 number
+    pub fn next_u32(&mut self) -> u32 {
+        if self.position + 4 > self.block.len() {
+            self.next_block();
+        }
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.block[self.position..self.position + 4]);
+        self.position += 4;
+        u32::from_le_bytes(bytes)
+    }
+
+    // Get uniform random number from 0 to max-1 (rejection sampling)
+    pub fn range(&mut self, max: u32) -> u32 {
+        if max == 0 {
+            panic!("Random range should not be empty");
+        }
+        if max == 1 {
+            return 0;
+        }
+
+        let zone = u32::MAX - (u32::MAX % max);
+        loop {
+            let value = self.next_u32();
+            if value < zone {
+                return value % max;
+            }
+        }
+    }
+
+    // Get random index with probability proportional to its weight
+    pub fn pick_weighted(&mut self, weights: &[u32]) -> usize {
+        let total: u32 = weights.iter().sum();
+        let mut value = self.range(total);
+        for (index, weight) in weights.iter().enumerate() {
+            if value < *weight {
+                return index;
+            }
+            value -= weight;
+        }
+        unreachable!()
+    }
+
+    // Get uniform random number from min to max (inclusive)
+    pub fn range_inclusive(&mut self, min: u32, max: u32) -> u32 {
+        min + self.range(max - min + 1)
+    }
+}
+
+impl Contract {
+    // Create new random stream for account & mine, each call gets unique nonce
+    pub(crate) fn random_stream(&mut self, account_id: &AccountId, mine_id: &TokenId) -> RandomStream {
+        self.random_nonce += 1;
+        RandomStream::new(&env::random_seed(), account_id, mine_id, self.random_nonce)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    const SAMPLES: u32 = 100_000;
+
+    fn setup() {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(accounts(0));
+        // Sampling makes a lot of sha256 calls, don't charge gas for them
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free());
+    }
+
+    fn stream(nonce: u64) -> RandomStream {
+        RandomStream::new(&[7u8; 32], "alice", "s-1-1", nonce)
+    }
+
+    #[test]
+    fn range_is_in_bounds() {
+        setup();
+        let mut random = stream(1);
+        for max in 1..=300 {
+            for _ in 0..20 {
+                assert!(random.range(max) < max);
+            }
+        }
+        for _ in 0..1000 {
+            let value = random.range_inclusive(1, 6);
+            assert!((1..=6).contains(&value));
+        }
+    }
+
+    #[test]
+    fn range_is_uniform() {
+        setup();
+        let mut random = stream(1);
+        let max = 100;
+        let mut buckets = vec![0u32; max as usize];
+        for _ in 0..SAMPLES {
+            buckets[random.range(max) as usize] += 1;
+        }
+
+        // Expected 1000 per bucket, allow 20% deviation
+        let expected = SAMPLES / max;
+        for count in buckets {
+            assert!(count > expected * 8 / 10 && count < expected * 12 / 10, "bucket count {}", count);
+        }
+    }
+
+    #[test]
+    fn range_upper_values_reachable() {
+        setup();
+        let mut random = stream(1);
+        let mut max_value = 0;
+        for _ in 0..10_000 {
+            max_value = max_value.max(random.range(100));
+        }
+        assert_eq!(max_value, 99);
+    }
+
+    #[test]
+    fn rarity_distribution_matches_drop_rates() {
+        setup();
+        let mut random = stream(1);
+        let rates = DropRates::default_for(&MineType::Small);
+        let mut legendary = 0;
+        let mut rare = 0;
+        let mut uncommon = 0;
+        let mut common = 0;
+        for _ in 0..SAMPLES {
+            match rates.pick_rarity(random.range(DROP_RATES_TOTAL)) {
+                CardRarity::Legendary => legendary += 1,
+                CardRarity::Rare => rare += 1,
+                CardRarity::UnCommon => uncommon += 1,
+                CardRarity::Common => common += 1,
+            }
+        }
+
+        // 1% / 5% / 24% / 70% of samples with 15% tolerance
+        let in_range = |count: u32, rate: u32| {
+            let expected = SAMPLES / DROP_RATES_TOTAL * rate;
+            count > expected * 85 / 100 && count < expected * 115 / 100
+        };
+        assert!(in_range(legendary, rates.legendary), "legendary {}", legendary);
+        assert!(in_range(rare, rates.rare), "rare {}", rare);
+        assert!(in_range(uncommon, rates.uncommon), "uncommon {}", uncommon);
+        assert!(in_range(common, rates.common), "common {}", common);
+    }
+
+    #[test]
+    fn weighted_pick_skips_zero_weights() {
+        setup();
+        let mut random = stream(1);
+        let mut counts = [0u32; 3];
+        for _ in 0..1000 {
+            counts[random.pick_weighted(&[1, 0, 3])] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[2] > counts[0] * 2);
+    }
+
+    #[test]
+    fn streams_with_different_input_differ() {
+        setup();
+        let values = |nonce: u64| -> Vec<u32> {
+            let mut random = stream(nonce);
+            (0..8).map(|_| random.next_u32()).collect()
+        };
+        let first = values(1);
+        let second = values(2);
+        let same = values(1);
+
+        assert_ne!(first, second);
+        assert_eq!(first, same);
+    }
+}
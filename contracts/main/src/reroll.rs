@@ -0,0 +1,155 @@
+use crate::*;
+
+// Reroll cost doubles with every reroll of the same stone
+pub const STONE_REROLL_MAX_DOUBLINGS: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum StoneAttribute {
+    Hardness,
+    Density,
+    Durability,
+}
+
+impl StoneAttribute {
+    pub fn range(&self) -> (u8, u8) {
+        match self {
+            StoneAttribute::Hardness => STONE_HARDNESS_RANGE,
+            StoneAttribute::Density => STONE_DENSITY_RANGE,
+            StoneAttribute::Durability => STONE_DURABILITY_RANGE,
+        }
+    }
+}
+
+impl Stone {
+    // Stone attributes stored in NFT metadata `extra`
+    pub fn metadata_extra(&self) -> String {
+        json!({
+            "hardness": self.hardness,
+            "density": self.density,
+            "durability": self.durability,
+            "kill_tokens": self.kill_tokens,
+        }).to_string()
+    }
+}
+
+impl Contract {
+    pub(crate) fn stone_reroll_cost(&self, stone_id: &TokenId) -> u128 {
+        let rerolls = self.stone_rerolls.get(stone_id).unwrap_or(0);
+        self.stone_reroll_base_cost.checked_mul(2u128.pow(rerolls.min(STONE_REROLL_MAX_DOUBLINGS)))
+            .expect("Reroll cost overflow, lower the base cost")
+    }
+
+    // Roll new attribute value, `amount` is MNL received with ft_transfer_call. Returns unused MNL amount.
+    pub(crate) fn reroll_stone_attribute(
+        &mut self,
+        owner_id: &AccountId,
+        stone_id: TokenId,
+        attribute: StoneAttribute,
+        amount: u128,
+    ) -> u128 {
+        let user_stones = self.user_stones.get(owner_id).unwrap_or(vec![]);
+        if !user_stones.contains(&stone_id) {
+            panic!("You don't own this stone");
+        }
+        if self.market.get(&stone_id).is_some() {
+            panic!("Remove stone from market before reroll");
+        }
+        // staking shares depend on attributes
+        self.assert_stone_unlocked(&stone_id);
+
+        let cost = self.stone_reroll_cost(&stone_id);
+        if amount < cost {
+            panic!("Reroll costs {} MNL yocto", cost);
+        }
+
+        let mut stone = self.stones.get(&stone_id).unwrap();
+        let (min, max) = attribute.range();
+        let mut random = self.random_stream(owner_id, &stone_id);
+        let value = random.range_inclusive(min as u32, max as u32) as u8;
+        match attribute {
+            StoneAttribute::Hardness => stone.hardness = value,
+            StoneAttribute::Density => stone.density = value,
+            StoneAttribute::Durability => stone.durability = value,
+        }
+        stone.kill_tokens = self.generate_stone_kill_tokens(
+            &stone.card_rarity, &stone.hardness, &stone.density, &stone.durability,
+        ).to_string();
+        self.stones.insert(&stone_id, &stone);
+
+        let rerolls = self.stone_rerolls.get(&stone_id).unwrap_or(0);
+        self.stone_rerolls.insert(&stone_id, &(rerolls + 1));
+
+        // Update NFT metadata
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_update_extra".to_vec(),
+            json!({
+                "token_id": stone_id,
+                "extra": stone.metadata_extra(),
+            }).to_string().as_bytes().to_vec(),
+            self.to_yocto("0.001"),
+            self.to_tera(10),
+        );
+        self.burn_mnl(cost, "Stone reroll");
+
+        amount - cost
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::MockedBlockchain;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn get_context(deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(1_000_000_000 * 60 * 60 * 24 * 10)
+            .attached_deposit(deposit);
+        builder
+    }
+
+    fn setup_stone(contract: &mut Contract) -> Stone {
+        testing_env!(get_context(contract.to_yocto("0.01")).build());
+        contract.add_new_collection("Collection 1".to_string(), "image".to_string(), vec!["1-1".to_string()]);
+        let mine_id = contract.mint_mine_nft().token_id;
+        contract.mint_free_stone_nft(mine_id).pop().unwrap()
+    }
+
+    #[test]
+    fn reroll_updates_stone_and_cost() {
+        testing_env!(get_context(0).build());
+        let mut contract = Contract::default();
+        let stone = setup_stone(&mut contract);
+        let base_cost = contract.stone_reroll_base_cost;
+
+        let unused = contract.reroll_stone_attribute(
+            &accounts(1).into(), stone.token_id.to_string(), StoneAttribute::Hardness, base_cost + 5,
+        );
+        assert_eq!(unused, 5);
+        assert_eq!(contract.stone_reroll_cost(&stone.token_id), base_cost * 2);
+
+        let rerolled = contract.stones.get(&stone.token_id).unwrap();
+        assert!((1..=6).contains(&rerolled.hardness));
+        assert_eq!(rerolled.density, stone.density);
+        let kill_tokens = contract.generate_stone_kill_tokens(
+            &rerolled.card_rarity, &rerolled.hardness, &rerolled.density, &rerolled.durability,
+        );
+        assert_eq!(rerolled.kill_tokens, kill_tokens.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Reroll costs")]
+    fn reroll_without_enough_mnl() {
+        testing_env!(get_context(0).build());
+        let mut contract = Contract::default();
+        let stone = setup_stone(&mut contract);
+        let base_cost = contract.stone_reroll_base_cost;
+        contract.reroll_stone_attribute(&accounts(1).into(), stone.token_id, StoneAttribute::Density, base_cost - 1);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::*;
+use crate::ft::FT_STORAGE_DEPOSIT;
+use crate::mine::{MINE_CLAIM_COOLDOWN, MINE_MINT_DEPOSIT};
+
+impl Contract {
+    // MNL price of the mine type, None when it can't be bought with MNL
+    pub(crate) fn mine_mnl_price(&self, mine_type: &MineType) -> Option<u128> {
+        self.mine_mnl_prices.get(mine_type)
+    }
+
+    // Buy mine with MNL received with ft_transfer_call, price is burned. Returns unused MNL amount.
+    pub(crate) fn buy_mine(&mut self, owner_id: &AccountId, mine_type: MineType, amount: u128) -> u128 {
+        let price = self.mine_mnl_price(&mine_type).expect("Mine type can't be bought with MNL");
+        if amount < price {
+            panic!("Mine costs {} MNL yocto", price);
+        }
+
+        // nft_mint deposit and token registration are paid from NEAR deposited by the buyer,
+        // not from the contract balance
+        let mint_deposit = self.to_yocto(MINE_MINT_DEPOSIT);
+        let mut required = mint_deposit;
+        if self.token_storage_required(owner_id) {
+            required += self.to_yocto(FT_STORAGE_DEPOSIT);
+        }
+        let near_deposit = self.mine_mint_deposits.get(owner_id).unwrap_or(0);
+        if near_deposit < required {
+            panic!("Deposit {} yoctoNEAR with deposit_mine_mint before buying mine", required);
+        }
+        self.save_mine_mint_deposit(owner_id, near_deposit - required);
+
+        let (mine_metadata, _) = self.create_mine(owner_id.to_string(), mine_type);
+        self.mint_mine_token(owner_id, mine_metadata, mint_deposit, self.to_tera(25));
+        self.burn_mnl(price, "Mine purchase");
+
+        amount - price
+    }
+
+    // NEAR deposited for nft_mint of mines bought with MNL, storage of a new entry is paid from
+    // the deposit. Returns deposited balance.
+    pub(crate) fn add_mine_mint_deposit(&mut self, account_id: &AccountId, amount: Balance) -> Balance {
+        let initial_storage_usage = env::storage_usage();
+        let deposit = self.mine_mint_deposits.get(account_id).unwrap_or(0);
+        self.mine_mint_deposits.insert(account_id, &(deposit + amount));
+
+        let storage_cost = (env::storage_usage() - initial_storage_usage) as Balance * env::storage_byte_cost();
+        if amount <= storage_cost {
+            panic!("Attach more than {} yoctoNEAR to cover storage", storage_cost);
+        }
+        let balance = deposit + amount - storage_cost;
+        self.mine_mint_deposits.insert(account_id, &balance);
+        balance
+    }
+
+    // Return deposited NEAR with released storage. Returns withdrawn amount.
+    pub(crate) fn refund_mine_mint_deposit(&mut self, account_id: &AccountId) -> Balance {
+        let initial_storage_usage = env::storage_usage();
+        let deposit = self.mine_mint_deposits.remove(account_id).expect("Nothing to withdraw");
+        let released = (initial_storage_usage - env::storage_usage()) as Balance * env::storage_byte_cost();
+
+        let amount = deposit + released;
+        Promise::new(account_id.to_string()).transfer(amount);
+        amount
+    }
+
+    fn save_mine_mint_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            self.mine_mint_deposits.remove(account_id);
+        } else {
+            self.mine_mint_deposits.insert(account_id, &amount);
+        }
+    }
+
+    // Make mine claimable right away, cost is burned. Returns unused MNL amount.
+    pub(crate) fn speed_up_claim(&mut self, owner_id: &AccountId, mine_id: TokenId, amount: u128) -> u128 {
+        let user_mines = self.user_mines.get(owner_id).unwrap_or(vec![]);
+        if !user_mines.contains(&mine_id) {
+            panic!("You don't have this Mine");
+        }
+        let mut mine = self.mines.get(&mine_id).unwrap();
+        if mine.last_stone_claim + MINE_CLAIM_COOLDOWN <= env::block_timestamp() {
+            panic!("Mine can be claimed already");
+        }
+        let cost = self.claim_speed_up_cost;
+        if amount < cost {
+            panic!("Speed up costs {} MNL yocto", cost);
+        }
+
+        mine.last_stone_claim = 0;
+        self.mines.insert(&mine_id, &mine);
+        self.burn_mnl(cost, "Claim speed up");
+
+        amount - cost
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    const MNL: u128 = 1_000_000_000_000_000_000_000_000;
+
+    #[test]
+    fn buy_mine_returns_unused() {
+        let mut contract = setup_contract();
+        let deposit = contract.add_mine_mint_deposit(&accounts(2).into(), contract.to_yocto("0.02"));
+        let unused = contract.buy_mine(&accounts(2).into(), MineType::Medium, 50 * MNL + 7);
+        assert_eq!(unused, 7);
+        let mines = contract.user_mines(accounts(2));
+        assert_eq!(mines.len(), 1);
+        assert_eq!(mines[0].mine_type, MineType::Medium);
+        let spent = contract.to_yocto(MINE_MINT_DEPOSIT) + contract.to_yocto(FT_STORAGE_DEPOSIT);
+        assert_eq!(contract.get_mine_mint_deposit(accounts(2)).0, deposit - spent);
+    }
+
+    #[test]
+    fn mine_mint_deposit_pays_storage_and_withdraws() {
+        let mut contract = setup_contract();
+        let amount = contract.to_yocto("0.02");
+        let deposit = contract.add_mine_mint_deposit(&accounts(2).into(), amount);
+        assert!(deposit < amount);
+        assert_eq!(contract.add_mine_mint_deposit(&accounts(2).into(), amount), deposit + amount);
+
+        assert_eq!(contract.refund_mine_mint_deposit(&accounts(2).into()), 2 * amount);
+        assert_eq!(contract.get_mine_mint_deposit(accounts(2)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "before buying mine")]
+    fn buy_mine_requires_near_deposit() {
+        let mut contract = setup_contract();
+        contract.buy_mine(&accounts(2).into(), MineType::Medium, 50 * MNL);
+    }
+
+    #[test]
+    fn speed_up_claim_resets_cooldown() {
+        let mut contract = setup_contract();
+        set_context(2, contract.to_yocto("0.01"));
+        let mine_id = contract.mint_mine_nft().token_id;
+        contract.mint_free_stone_nft(mine_id.to_string());
+
+        assert_eq!(contract.speed_up_claim(&accounts(2).into(), mine_id.to_string(), MNL), 0);
+        assert_eq!(contract.mines.get(&mine_id).unwrap().last_stone_claim, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mine type can't be bought with MNL")]
+    fn small_mine_not_for_sale() {
+        let mut contract = setup_contract();
+        contract.buy_mine(&accounts(2).into(), MineType::Small, 100 * MNL);
+    }
+}
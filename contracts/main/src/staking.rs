@@ -0,0 +1,254 @@
+use near_sdk::{PromiseResult, Timestamp};
+
+use crate::*;
+
+// Storage deposit for locking one stone on nft-stone contract
+pub const STAKE_LOCK_DEPOSIT: &str = "0.001";
+
+#[derive(Debug, Default, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Staker {
+    pub shares: u128,
+    pub reward_debt: u128,
+    pub unclaimed: u128,
+    pub stone_ids: Vec<TokenId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingPool {
+    pub reward_per_second: u128,
+    pub total_shares: u128,
+    pub acc_reward_per_share: u128,
+    pub last_reward_time: Timestamp,
+}
+
+impl StakingPool {
+    pub fn new(reward_per_second: u128) -> Self {
+        Self {
+            reward_per_second,
+            total_shares: 0,
+            acc_reward_per_share: 0,
+            last_reward_time: 0,
+        }
+    }
+
+    // Reward per share accumulated up to timestamp
+    pub fn acc_reward_per_share_at(&self, timestamp: Timestamp) -> u128 {
+        if self.total_shares == 0 || timestamp <= self.last_reward_time {
+            return self.acc_reward_per_share;
+        }
+        let seconds = ((timestamp - self.last_reward_time) / 1_000_000_000) as u128;
+        self.acc_reward_per_share + seconds * self.reward_per_second / self.total_shares
+    }
+
+    pub fn update(&mut self, timestamp: Timestamp) {
+        self.acc_reward_per_share = self.acc_reward_per_share_at(timestamp);
+        // keep remainder of the second for the next update
+        if self.total_shares == 0 {
+            self.last_reward_time = timestamp;
+        } else if timestamp > self.last_reward_time {
+            let seconds = (timestamp - self.last_reward_time) / 1_000_000_000;
+            self.last_reward_time += seconds * 1_000_000_000;
+        }
+    }
+}
+
+impl Stone {
+    // Staking weight of the stone
+    pub fn staking_shares(&self) -> u128 {
+        let rarity: u128 = match self.card_rarity {
+            CardRarity::Legendary => 70,
+            CardRarity::Rare => 30,
+            CardRarity::UnCommon => 8,
+            CardRarity::Common => 2
+        };
+        rarity * self.hardness as u128
+    }
+}
+
+impl Staker {
+    pub fn pending_rewards(&self, acc_reward_per_share: u128) -> u128 {
+        self.unclaimed + self.shares * acc_reward_per_share - self.reward_debt
+    }
+}
+
+impl Contract {
+    // Update pool and move user pending rewards to unclaimed
+    fn staking_settle(&mut self, account_id: &AccountId) -> Staker {
+        self.staking_pool.update(env::block_timestamp());
+        let acc_reward_per_share = self.staking_pool.acc_reward_per_share;
+
+        let mut staker = self.stakers.get(account_id).unwrap_or_default();
+        staker.unclaimed = staker.pending_rewards(acc_reward_per_share);
+        staker.reward_debt = staker.shares * acc_reward_per_share;
+        staker
+    }
+
+    fn staking_save(&mut self, account_id: &AccountId, mut staker: Staker) {
+        staker.reward_debt = staker.shares * self.staking_pool.acc_reward_per_share;
+        if staker.stone_ids.is_empty() && staker.unclaimed == 0 {
+            self.stakers.remove(account_id);
+        } else {
+            self.stakers.insert(account_id, &staker);
+        }
+    }
+
+    pub(crate) fn stake(&mut self, account_id: &AccountId, stone_ids: Vec<TokenId>) {
+        let lock_deposit = self.to_yocto(STAKE_LOCK_DEPOSIT) * stone_ids.len() as u128;
+        if env::attached_deposit() < lock_deposit {
+            panic!("Attach {} yoctoNEAR to lock stones", lock_deposit);
+        }
+
+        let user_stones = self.user_stones.get(account_id).unwrap_or(vec![]);
+        let mut staker = self.staking_settle(account_id);
+
+        for stone_id in stone_ids.iter() {
+            if !user_stones.contains(stone_id) {
+                panic!("You don't own this stone");
+            }
+            self.assert_stone_unlocked(stone_id);
+            if self.market.get(stone_id).is_some() {
+                panic!("Remove stone from market before staking");
+            }
+
+            let stone = self.stones.get(stone_id).unwrap();
+            let shares = stone.staking_shares();
+            staker.shares += shares;
+            staker.stone_ids.push(stone_id.to_string());
+            self.staking_pool.total_shares += shares;
+            self.staked_stones.insert(stone_id);
+        }
+        self.staking_save(account_id, staker);
+
+        // Lock stones on NFT contract
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_lock_tokens".to_vec(),
+            json!({
+                "token_ids": stone_ids,
+                "owner_id": account_id,
+            }).to_string().as_bytes().to_vec(),
+            env::attached_deposit(),
+            self.to_tera(10),
+        );
+    }
+
+    pub(crate) fn unstake(&mut self, account_id: &AccountId, stone_ids: Vec<TokenId>) {
+        let mut staker = self.staking_settle(account_id);
+
+        for stone_id in stone_ids.iter() {
+            let index = staker.stone_ids.iter().position(|id| id == stone_id)
+                .expect("Stone is not staked");
+            staker.stone_ids.remove(index);
+
+            let stone = self.stones.get(stone_id).unwrap();
+            let shares = stone.staking_shares();
+            staker.shares -= shares;
+            self.staking_pool.total_shares -= shares;
+            self.staked_stones.remove(stone_id);
+        }
+        self.staking_save(account_id, staker);
+
+        // Unlock stones on NFT contract
+        Promise::new(self.contract_nft_stone.clone()).function_call(
+            b"nft_unlock_tokens".to_vec(),
+            json!({
+                "token_ids": stone_ids,
+                "owner_id": account_id,
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(10),
+        );
+    }
+
+    pub(crate) fn claim_staking(&mut self, account_id: &AccountId) -> u128 {
+        let mut staker = self.staking_settle(account_id);
+        let amount = staker.unclaimed;
+        if amount == 0 {
+            panic!("Nothing to claim");
+        }
+        staker.unclaimed = 0;
+        self.staking_save(account_id, staker);
+
+        // transfer MNL tokens, rewards are restored in on_staking_claimed if transfer fails
+        self.transfer_mnl(account_id, amount).then(Promise::new(env::current_account_id()).function_call(
+            b"on_staking_claimed".to_vec(),
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(5),
+        ));
+
+        amount
+    }
+
+    pub(crate) fn resolve_staking_claim(&mut self, account_id: &AccountId, amount: u128) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+
+        let mut staker = self.staking_settle(account_id);
+        staker.unclaimed += amount;
+        self.staking_save(account_id, staker);
+        false
+    }
+
+    pub(crate) fn staking_pending(&self, account_id: &AccountId) -> u128 {
+        let acc_reward_per_share = self.staking_pool.acc_reward_per_share_at(env::block_timestamp());
+        self.stakers.get(account_id)
+            .map(|staker| staker.pending_rewards(acc_reward_per_share))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    const SECOND: Timestamp = 1_000_000_000;
+
+    #[test]
+    fn rewards_split_by_shares() {
+        let mut pool = StakingPool::new(100);
+        pool.update(0);
+
+        // first staker with 1 share for 10 seconds
+        let mut first = Staker { shares: 1, ..Default::default() };
+        pool.total_shares = 1;
+
+        // second staker joins with 3 shares
+        pool.update(10 * SECOND);
+        let mut second = Staker { shares: 3, ..Default::default() };
+        second.reward_debt = second.shares * pool.acc_reward_per_share;
+        pool.total_shares = 4;
+
+        // 10 more seconds, partial second is kept for the next update
+        pool.update(20 * SECOND + SECOND / 2);
+        assert_eq!(pool.last_reward_time, 20 * SECOND);
+        assert_eq!(first.pending_rewards(pool.acc_reward_per_share), 1000 + 250);
+        assert_eq!(second.pending_rewards(pool.acc_reward_per_share), 750);
+
+        first.unclaimed = first.pending_rewards(pool.acc_reward_per_share);
+        first.reward_debt = first.shares * pool.acc_reward_per_share;
+        assert_eq!(first.pending_rewards(pool.acc_reward_per_share), 1250);
+    }
+
+    #[test]
+    fn failed_claim_restores_rewards() {
+        let mut builder = VMContextBuilder::new();
+        builder.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![PromiseResult::Failed]);
+        let mut contract = Contract::default();
+        contract.stakers.insert(&accounts(2).into(), &Staker { unclaimed: 40, ..Default::default() });
+
+        assert_eq!(contract.claim_staking(&accounts(2).into()), 40);
+        assert_eq!(contract.staking_pending(&accounts(2).into()), 0);
+        assert!(!contract.on_staking_claimed(accounts(2).into(), U128(40)));
+        assert_eq!(contract.staking_pending(&accounts(2).into()), 40);
+    }
+}
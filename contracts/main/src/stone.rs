@@ -3,7 +3,12 @@ use std::string::ParseError;
 
 use crate::*;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+// Stone attribute ranges (inclusive)
+pub const STONE_HARDNESS_RANGE: (u8, u8) = (1, 6);
+pub const STONE_DENSITY_RANGE: (u8, u8) = (1, 3);
+pub const STONE_DURABILITY_RANGE: (u8, u8) = (0, 2);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum CardRarity {
     Common,
@@ -23,6 +28,32 @@ impl fmt::Display for CardRarity {
     }
 }
 
+impl CardRarity {
+    pub fn all() -> Vec<CardRarity> {
+        vec![CardRarity::Common, CardRarity::UnCommon, CardRarity::Rare, CardRarity::Legendary]
+    }
+
+    // Get next rarity up
+    pub fn higher(&self) -> Option<CardRarity> {
+        match self {
+            CardRarity::Common => Some(CardRarity::UnCommon),
+            CardRarity::UnCommon => Some(CardRarity::Rare),
+            CardRarity::Rare => Some(CardRarity::Legendary),
+            CardRarity::Legendary => None,
+        }
+    }
+
+    // Get next rarity down
+    pub fn lower(&self) -> Option<CardRarity> {
+        match self {
+            CardRarity::Legendary => Some(CardRarity::Rare),
+            CardRarity::Rare => Some(CardRarity::UnCommon),
+            CardRarity::UnCommon => Some(CardRarity::Common),
+            CardRarity::Common => None,
+        }
+    }
+}
+
 impl FromStr for CardRarity {
     type Err = ParseError;
 
@@ -54,8 +85,8 @@ pub struct Stone {
 }
 
 impl Stone {
-    pub fn get_kill_tokens(&self, timestamp: u64) -> u128 {
-        let token_count = 100_000_000_000_000_000_000_000; // 0.1 MNL
+    // `token_count` is MNL added for each doubling of stone age
+    pub fn get_kill_tokens(&self, timestamp: u64, token_count: u128) -> u128 {
         let duration = timestamp - self.mint_date;
         let coefficient = fast_math::log2_raw(duration as f32) as u128;
 
@@ -71,10 +102,7 @@ impl Contract {
     }
 
     pub(crate) fn stones_metadata(&mut self, mint_count: u8, mine_id: TokenId) -> (Vec<JsonValue>, Vec<Stone>) {
-        let mut metadata: Vec<JsonValue> = vec![];
-        let mut new_stones: Vec<Stone> = vec![];
-        let owner_id = env::predecessor_account_id().to_string();
-        let mut user_stones: Vec<TokenId> = self.user_stones.get(&owner_id).unwrap_or(vec![]);
+        let owner_id = env::predecessor_account_id();
 
         let current_mine: Mine = self.mines.get(&mine_id).unwrap();
         let user_mines = self.user_mines.get(&owner_id).unwrap();
@@ -82,126 +110,159 @@ impl Contract {
             panic!("You don't have this Mine");
         }
 
-        for num in 1..=mint_count {
-            let num = num as u32;
-            let collection_id: u32 = self.generate_stone_random_collection(num as usize);
-            let (media_url, collection_index) = self.generate_stone_media(collection_id, num as usize);
-            let token_id = format!("zm-{}-{}{}", self.stone_minted_count + 1, self.random_u8(0), num);
-            let title = format!("Stone #{}", self.stone_minted_count + 1);
-
-            metadata.push(json!({
-                "token_id": token_id,
-                "receiver_id": owner_id,
-                "token_metadata": {
-                    "title": title,
-                    "media": media_url,
-                    "copies": 1
-                }
-            }));
-
-            // Add for user
-            let card_rarity = self.generate_stone_card_rarity(&current_mine.mine_type, num as usize);
-            let hardness = self.generate_stone_hardness(num as usize);
-            let density = self.generate_stone_density(num as usize);
-            let durability = self.generate_stone_durability(num as usize);
-            let kill_tokens = self.generate_stone_kill_tokens(&card_rarity, &hardness, &density, &durability);
-
-            // Add stone to user Rarity & Collection
-            self.stone_add_user_rarity(&owner_id, &token_id, &card_rarity);
-            self.stone_add_user_collection(&owner_id, &token_id, &collection_id);
-
-            let stone = Stone {
-                token_id: token_id.to_string(),
-                mint_date: env::block_timestamp(),
-                sale_price: None,
-                kill_tokens: kill_tokens.to_string(),
-                media: media_url,
-                collection_id,
-                collection_index,
-                card_rarity,
-                hardness,
-                density,
-                durability,
-            };
-            self.stones.insert(&token_id, &stone);
-            user_stones.push(token_id.to_string());
-            new_stones.push(stone);
+        let drop_rates = self.mine_drop_rates(&current_mine.mine_type);
+        let mut random = self.random_stream(&owner_id, &mine_id);
+        let result = self.generate_stones(&owner_id, mint_count, &current_mine.mine_type, &drop_rates, &mut random);
 
-            // increase total count
-            self.stone_minted_count += 1;
+        // update mine claim timestamp
+        self.update_mine_claim_timestamp(current_mine);
+
+        result
+    }
+
+    pub(crate) fn stone_mint_deposit(&self, mint_count: u8) -> Balance {
+        match mint_count {
+            1 => self.to_yocto("0.01"),
+            4 => self.to_yocto("0.03"),
+            8 => self.to_yocto("0.06"),
+            _ => panic!("Wrong mint_count")
         }
+    }
 
-        self.user_stones.insert(&owner_id, &user_stones);
+    pub(crate) fn mint_stones_promise(&self, stones_metadata: Vec<JsonValue>, deposit: Balance) -> Promise {
+        let mint_gas: Gas = 20 + (stones_metadata.len() * 8) as u64;
+        Promise::new(self.contract_nft_stone.to_string()).function_call(
+            b"mint_multiple_stones".to_vec(),
+            json!({
+                "stones_metadata": stones_metadata
+            }).to_string().as_bytes().to_vec(),
+            deposit,
+            self.to_tera(mint_gas),
+        )
+    }
 
-        // update mine claim timestamp
-        self.update_mine_claim_timestamp(current_mine);
+    pub(crate) fn generate_stones(
+        &mut self,
+        owner_id: &AccountId,
+        mint_count: u8,
+        mine_type: &MineType,
+        drop_rates: &DropRates,
+        random: &mut RandomStream,
+    ) -> (Vec<JsonValue>, Vec<Stone>) {
+        let mut metadata: Vec<JsonValue> = vec![];
+        let mut new_stones: Vec<Stone> = vec![];
+
+        for num in 1..=mint_count {
+            let card_rarity = self.generate_stone_card_rarity(drop_rates, random);
+            let collection_id: u32 = self.generate_stone_random_collection(mine_type, &card_rarity, random);
+            let card_rarity = self.stone_rarity_with_supply(collection_id, card_rarity);
+            let hardness = self.generate_stone_hardness(random);
+            let density = self.generate_stone_density(random);
+            let durability = self.generate_stone_durability(random);
+
+            let collection_index = self.generate_stone_index(collection_id, random);
+            let (stone_metadata, stone) = self.create_stone(
+                owner_id, num as u32, collection_id, card_rarity, (hardness, density, durability), collection_index,
+            );
+            metadata.push(stone_metadata);
+            new_stones.push(stone);
+        }
 
         (metadata, new_stones)
     }
 
-    pub(crate) fn generate_stone_card_rarity(&self, mine_type: &MineType, num: usize) -> CardRarity {
-        let rand_val = self.random_u8_range(num, 100) as u32 * 10;
-        let result: CardRarity;
-
-        if mine_type == &MineType::Small {
-            result = match rand_val {
-                0..=9 => CardRarity::Legendary,
-                10..=59 => CardRarity::Rare,
-                60..=299 => CardRarity::UnCommon,
-                _ => CardRarity::Common
-            };
-        } else if mine_type == &MineType::Medium {
-            result = match rand_val {
-                0..=11 => CardRarity::Legendary,
-                12..=71 => CardRarity::Rare,
-                72..=359 => CardRarity::UnCommon,
-                _ => CardRarity::Common
-            };
-        } else {
-            result = match rand_val {
-                0..=14 => CardRarity::Legendary,
-                15..=89 => CardRarity::Rare,
-                90..=449 => CardRarity::UnCommon,
-                _ => CardRarity::Common
-            };
-        }
+    // Create new stone for user, returns NFT metadata for minting and the stone
+    pub(crate) fn create_stone(
+        &mut self,
+        owner_id: &AccountId,
+        num: u32,
+        collection_id: u32,
+        card_rarity: CardRarity,
+        (hardness, density, durability): (u8, u8, u8),
+        collection_index: u8,
+    ) -> (JsonValue, Stone) {
+        let media_url = self.collections.get(&collection_id).unwrap().stone_media(collection_index as usize, &card_rarity);
+        let token_id = format!("zm-{}-{}{}", self.stone_minted_count + 1, self.random_u8(0), num);
+        let title = format!("Stone #{}", self.stone_minted_count + 1);
+
+        let kill_tokens = self.generate_stone_kill_tokens(&card_rarity, &hardness, &density, &durability);
+        let stone = Stone {
+            token_id,
+            mint_date: env::block_timestamp(),
+            sale_price: None,
+            kill_tokens: kill_tokens.to_string(),
+            media: media_url,
+            collection_id,
+            collection_index,
+            card_rarity,
+            hardness,
+            density,
+            durability,
+        };
 
-        result
+        let royalty = self.collections.get(&collection_id).unwrap().royalty;
+        let metadata = json!({
+            "token_id": stone.token_id,
+            "receiver_id": owner_id,
+            "perpetual_royalties": royalty,
+            "token_metadata": {
+                "title": title,
+                "media": stone.media,
+                "copies": 1,
+                "extra": stone.metadata_extra()
+            }
+        });
+
+        // Add stone to user, Rarity & Collection
+        self.stone_add_to_user(&stone, owner_id);
+        self.stone_supply_add_minted(collection_id, &stone.card_rarity);
+
+        // increase total count
+        self.stone_minted_count += 1;
+
+        (metadata, stone)
     }
 
-    // Get random stone type
-    pub(crate) fn generate_stone_random_collection(&self, num: usize) -> u32 {
-        let rand_index = self.random_u8_range(0 + num, self.collections.len() as usize);
-        let collection_ids: Vec<u32> = self.collections.keys().collect();
-        *collection_ids.get(rand_index as usize).unwrap()
+    pub(crate) fn generate_stone_card_rarity(&self, drop_rates: &DropRates, random: &mut RandomStream) -> CardRarity {
+        let rand_val = random.range(DROP_RATES_TOTAL);
+        drop_rates.pick_rarity(rand_val)
     }
 
-    pub(crate) fn generate_stone_media(&self, collection: u32, num: usize) -> (String, u8) {
-        let collection = self.collections.get(&collection).unwrap();
-        let mut stone_index = self.random_u8_range(2 + num, collection.stone_images.len());
-        if stone_index as usize >= collection.stone_images.len() {
-            stone_index = 0;
+    // Get random stone type from active collections available for the mine type, by collection weight.
+    // Collections without supply for the rolled rarity (or lower) are skipped.
+    pub(crate) fn generate_stone_random_collection(&self, mine_type: &MineType, card_rarity: &CardRarity, random: &mut RandomStream) -> u32 {
+        let collection_weights: Vec<(u32, u32)> = self.collection_drop_weights(mine_type).into_iter()
+            .filter(|(collection_id, _)| self.stone_rarity_available(*collection_id, card_rarity.clone()).is_some())
+            .collect();
+        if collection_weights.is_empty() {
+            panic!("Stone supply is exhausted");
         }
-        let media_url = collection.stone_images[stone_index as usize].to_string();
-        (media_url, stone_index)
+        let weights: Vec<u32> = collection_weights.iter().map(|(_, weight)| *weight).collect();
+        collection_weights[random.pick_weighted(&weights)].0
+    }
+
+    // Get random stone image index by image weight
+    pub(crate) fn generate_stone_index(&self, collection: u32, random: &mut RandomStream) -> u8 {
+        let collection = self.collections.get(&collection).unwrap();
+        random.pick_weighted(&collection.stone_image_weights) as u8
     }
 
     // 1 - 6
-    pub(crate) fn generate_stone_hardness(&self, num: usize) -> u8 {
-        let rand_val = self.random_u8_range(5 + num, 5);
-        rand_val + 1
+    pub(crate) fn generate_stone_hardness(&self, random: &mut RandomStream) -> u8 {
+        let (min, max) = STONE_HARDNESS_RANGE;
+        random.range_inclusive(min as u32, max as u32) as u8
     }
 
     // 1 - 3
-    pub(crate) fn generate_stone_density(&self, num: usize) -> u8 {
-        let rand_val = self.random_u8_range(10 + num, 2);
-        rand_val + 1
+    pub(crate) fn generate_stone_density(&self, random: &mut RandomStream) -> u8 {
+        let (min, max) = STONE_DENSITY_RANGE;
+        random.range_inclusive(min as u32, max as u32) as u8
     }
 
     // 0 - 2
-    pub(crate) fn generate_stone_durability(&self, num: usize) -> u8 {
-        let rand_val = self.random_u8_range(15 + num, 2);
-        rand_val
+    pub(crate) fn generate_stone_durability(&self, random: &mut RandomStream) -> u8 {
+        let (min, max) = STONE_DURABILITY_RANGE;
+        random.range_inclusive(min as u32, max as u32) as u8
     }
 
     pub(crate) fn generate_stone_kill_tokens(&self, card_rarity: &CardRarity, hardness: &u8, density: &u8, durability: &u8) -> u128 {
@@ -215,22 +276,51 @@ impl Contract {
         return (hardness + density + durability) as u128 * rarity * one_token;
     }
 
+    pub(crate) fn assert_stone_unlocked(&self, token_id: &TokenId) {
+        if self.staked_stones.contains(token_id) {
+            panic!("Stone is staked");
+        }
+        if self.equipped_stones.contains_key(token_id) {
+            panic!("Stone is equipped");
+        }
+    }
+
     pub(crate) fn stone_remove_from_user(&mut self, stone: &Stone, owner_id: &AccountId) {
-        let mut user_stones = self.user_stones.get(owner_id).unwrap();
+        let user_stones = self.user_stones.get(owner_id).unwrap();
         if !user_stones.contains(&stone.token_id) {
             panic!("User don't own this stone");
         }
+        self.assert_stone_unlocked(&stone.token_id);
+
+        self.stone_unlink_from_user(stone, owner_id);
+        self.stone_killed_count += 1;
+        self.stone_supply_add_burned(stone.collection_id, &stone.card_rarity);
+    }
+
+    // Add stone to user stones, rarities & collections
+    pub(crate) fn stone_add_to_user(&mut self, stone: &Stone, owner_id: &AccountId) {
+        let mut user_stones = self.user_stones.get(owner_id).unwrap_or(vec![]);
+        user_stones.push(stone.token_id.to_string());
+        self.user_stones.insert(owner_id, &user_stones);
+
+        self.stone_add_user_rarity(owner_id, &stone.token_id, &stone.card_rarity);
+        self.stone_add_user_collection(owner_id, &stone.token_id, &stone.collection_id);
 
+        self.stones.insert(&stone.token_id, stone);
+    }
+
+    // Remove stone from user stones, rarities & collections
+    pub(crate) fn stone_unlink_from_user(&mut self, stone: &Stone, owner_id: &AccountId) {
+        let mut user_stones = self.user_stones.get(owner_id).unwrap();
         let index = user_stones.iter().position(|stone_id| &stone.token_id == stone_id).unwrap();
         user_stones.remove(index);
         self.user_stones.insert(owner_id, &user_stones);
 
         // Remove from user rarities & collections
-        self.stone_remove_user_rarities(&owner_id, stone);
-        self.stone_remove_user_collections(&owner_id, stone);
+        self.stone_remove_user_rarities(owner_id, stone);
+        self.stone_remove_user_collections(owner_id, stone);
 
         self.stones.remove(&stone.token_id);
-        self.stone_killed_count += 1;
     }
 
     pub fn stone_add_user_rarity(&mut self, owner_id: &AccountId, token_id: &TokenId, card_rarity: &CardRarity) {
@@ -271,20 +361,13 @@ impl Contract {
         self.user_stone_by_rarity.insert(&owner_id, &all_rarities);
     }
 
-    pub(crate) fn stone_remove_token_transfer(&self, stone: Stone) -> String {
-        let kill_tokens = stone.get_kill_tokens(env::block_timestamp());
+    pub(crate) fn stone_remove_token_transfer(&mut self, stone: Stone) -> String {
+        let kill_tokens = stone.get_kill_tokens(env::block_timestamp(), self.kill_reward);
 
-        // transfer MNL tokens
-        let ft_mint_gas: Gas = self.to_tera(10);
-        Promise::new(self.contract_ft.to_string()).function_call(
-            b"ft_transfer".to_vec(),
-            json!({
-                "receiver_id": &env::predecessor_account_id(),
-                "amount": kill_tokens.to_string()
-            }).to_string().as_bytes().to_vec(),
-            1,
-            ft_mint_gas,
-        );
+        // mint MNL tokens
+        if kill_tokens > 0 {
+            self.pay_mnl_reward(&env::predecessor_account_id(), kill_tokens, "Stone kill");
+        }
 
         // remove NFT and free storage
         let call_gas: Gas = self.to_tera(30);
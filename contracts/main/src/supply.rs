@@ -0,0 +1,159 @@
+use crate::*;
+
+// Supply caps limit the total number of minted stones, burned stones don't free up supply
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SupplyScope {
+    Rarity(CardRarity),
+    Collection(u32),
+    CollectionRarity(u32, CardRarity),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StoneSupply {
+    pub cap: Option<u32>,
+    pub minted: u32,
+    pub burned: u32,
+    pub remaining: Option<u32>,
+}
+
+impl Contract {
+    pub(crate) fn set_supply_cap(&mut self, scope: SupplyScope, cap: Option<u32>) {
+        match cap {
+            Some(cap) => self.stone_supply_caps.insert(&scope, &cap),
+            None => self.stone_supply_caps.remove(&scope),
+        };
+    }
+
+    pub(crate) fn supply_remaining(&self, scope: &SupplyScope) -> Option<u32> {
+        self.stone_supply_caps.get(scope).map(|cap| {
+            cap.saturating_sub(self.stone_supply_minted.get(scope).unwrap_or(0))
+        })
+    }
+
+    pub(crate) fn supply_available(&self, scope: &SupplyScope) -> bool {
+        self.supply_remaining(scope) != Some(0)
+    }
+
+    // Check if stone with rarity can be minted in collection
+    pub(crate) fn stone_supply_available(&self, collection_id: u32, card_rarity: &CardRarity) -> bool {
+        self.supply_available(&SupplyScope::Rarity(card_rarity.clone()))
+            && self.supply_available(&SupplyScope::Collection(collection_id))
+            && self.supply_available(&SupplyScope::CollectionRarity(collection_id, card_rarity.clone()))
+    }
+
+    // Get highest rarity (starting from rolled one) which has supply left
+    pub(crate) fn stone_rarity_available(&self, collection_id: u32, card_rarity: CardRarity) -> Option<CardRarity> {
+        let mut card_rarity = card_rarity;
+        while !self.stone_supply_available(collection_id, &card_rarity) {
+            card_rarity = card_rarity.lower()?;
+        }
+        Some(card_rarity)
+    }
+
+    pub(crate) fn stone_rarity_with_supply(&self, collection_id: u32, card_rarity: CardRarity) -> CardRarity {
+        self.stone_rarity_available(collection_id, card_rarity).expect("Stone supply is exhausted")
+    }
+
+    pub(crate) fn stone_supply_add_minted(&mut self, collection_id: u32, card_rarity: &CardRarity) {
+        for scope in Self::stone_supply_scopes(collection_id, card_rarity) {
+            let minted = self.stone_supply_minted.get(&scope).unwrap_or(0);
+            self.stone_supply_minted.insert(&scope, &(minted + 1));
+        }
+    }
+
+    // Free supply of stone which was never minted on nft contract
+    pub(crate) fn stone_supply_sub_minted(&mut self, collection_id: u32, card_rarity: &CardRarity) {
+        for scope in Self::stone_supply_scopes(collection_id, card_rarity) {
+            let minted = self.stone_supply_minted.get(&scope).unwrap_or(0);
+            self.stone_supply_minted.insert(&scope, &minted.saturating_sub(1));
+        }
+    }
+
+    pub(crate) fn stone_supply_add_burned(&mut self, collection_id: u32, card_rarity: &CardRarity) {
+        for scope in Self::stone_supply_scopes(collection_id, card_rarity) {
+            let burned = self.stone_supply_burned.get(&scope).unwrap_or(0);
+            self.stone_supply_burned.insert(&scope, &(burned + 1));
+        }
+    }
+
+    pub(crate) fn stone_supply_sub_burned(&mut self, collection_id: u32, card_rarity: &CardRarity) {
+        for scope in Self::stone_supply_scopes(collection_id, card_rarity) {
+            let burned = self.stone_supply_burned.get(&scope).unwrap_or(0);
+            self.stone_supply_burned.insert(&scope, &burned.saturating_sub(1));
+        }
+    }
+
+    pub(crate) fn get_stone_supply(&self, scope: &SupplyScope) -> StoneSupply {
+        StoneSupply {
+            cap: self.stone_supply_caps.get(scope),
+            minted: self.stone_supply_minted.get(scope).unwrap_or(0),
+            burned: self.stone_supply_burned.get(scope).unwrap_or(0),
+            remaining: self.supply_remaining(scope),
+        }
+    }
+
+    fn stone_supply_scopes(collection_id: u32, card_rarity: &CardRarity) -> Vec<SupplyScope> {
+        vec![
+            SupplyScope::Rarity(card_rarity.clone()),
+            SupplyScope::Collection(collection_id),
+            SupplyScope::CollectionRarity(collection_id, card_rarity.clone()),
+        ]
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::MockedBlockchain;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn setup() -> Contract {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(accounts(0));
+        testing_env!(builder.build());
+        Contract::default()
+    }
+
+    #[test]
+    fn fallback_to_lower_rarity() {
+        let mut contract = setup();
+        contract.set_rarity_supply_cap(CardRarity::Legendary, Some(1));
+        contract.set_collection_rarity_supply_cap(1, CardRarity::Rare, Some(0));
+
+        assert_eq!(contract.stone_rarity_with_supply(1, CardRarity::Legendary), CardRarity::Legendary);
+        contract.stone_supply_add_minted(1, &CardRarity::Legendary);
+        assert_eq!(contract.stone_rarity_with_supply(1, CardRarity::Legendary), CardRarity::UnCommon);
+        assert_eq!(contract.stone_rarity_with_supply(2, CardRarity::Legendary), CardRarity::Rare);
+
+        let supply = contract.stone_supply(Some(1));
+        assert_eq!(supply.get("Legendary").unwrap().minted, 1);
+        assert_eq!(supply.get("Total").unwrap().minted, 1);
+        assert_eq!(supply.get("Rare").unwrap().remaining, Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Stone supply is exhausted")]
+    fn exhausted_supply() {
+        let mut contract = setup();
+        contract.set_collection_supply_cap(1, Some(0));
+        contract.stone_rarity_with_supply(1, CardRarity::Rare);
+    }
+
+    #[test]
+    fn claim_skips_exhausted_collection() {
+        let mut contract = setup();
+        contract.add_collection("Collection 1".to_string(), "image-1".to_string(), vec!["1-1".to_string()]);
+        contract.add_collection("Collection 2".to_string(), "image-2".to_string(), vec!["2-1".to_string()]);
+        contract.set_collection_rarity_supply_cap(1, CardRarity::Common, Some(0));
+
+        let mut random = RandomStream::new(&[7u8; 32], "alice", "s-1-1", 1);
+        for _ in 0..20 {
+            let collection_id = contract.generate_stone_random_collection(&MineType::Small, &CardRarity::Common, &mut random);
+            assert_eq!(collection_id, 2);
+        }
+    }
+}
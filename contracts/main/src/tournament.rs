@@ -0,0 +1,313 @@
+use crate::*;
+
+pub const TOURNAMENTS_DEFAULT_LIMIT: u64 = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TournamentStatus {
+    Registration,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentEntry {
+    pub account_id: AccountId,
+    pub monster_id: TokenId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentMatch {
+    pub first: TournamentEntry,
+    // None when the first player has a bye
+    pub second: Option<TournamentEntry>,
+    pub winner: TournamentEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tournament {
+    pub name: String,
+    pub entry_fee: U128,
+    pub max_players: u32,
+    // Prize pool share (basis points) for 1st, 2nd, ... place, the rest is the fee
+    pub prize_shares: Vec<u32>,
+    pub status: TournamentStatus,
+    pub prize_pool: U128,
+    pub entries: Vec<TournamentEntry>,
+    // Players of the current round, ordered by seed
+    pub players: Vec<TournamentEntry>,
+    pub rounds: Vec<Vec<TournamentMatch>>,
+    // Final places, winner first
+    pub standings: Vec<TournamentEntry>,
+}
+
+impl Contract {
+    pub(crate) fn add_tournament(&mut self, name: String, entry_fee: u128, max_players: u32, prize_shares: Vec<u32>) -> u64 {
+        if max_players < 2 {
+            panic!("Tournament needs at least 2 players");
+        }
+        if prize_shares.is_empty() || prize_shares.len() > max_players as usize {
+            panic!("Wrong prize shares");
+        }
+        if prize_shares.iter().sum::<u32>() > 10_000 {
+            panic!("Prize shares should not exceed 100%");
+        }
+
+        self.tournament_count += 1;
+        self.tournaments.insert(&self.tournament_count, &Tournament {
+            name,
+            entry_fee: U128(entry_fee),
+            max_players,
+            prize_shares,
+            status: TournamentStatus::Registration,
+            prize_pool: U128(0),
+            entries: vec![],
+            players: vec![],
+            rounds: vec![],
+            standings: vec![],
+        });
+        self.tournament_count
+    }
+
+    // Register monster paying the entry fee, returns unused MNL amount
+    pub(crate) fn tournament_join(&mut self, account_id: &AccountId, tournament_id: u64, monster_id: TokenId, amount: u128) -> u128 {
+        let mut tournament = self.tournaments.get(&tournament_id).expect("Tournament not found");
+        if tournament.status != TournamentStatus::Registration {
+            panic!("Tournament registration is closed");
+        }
+        if tournament.entries.len() >= tournament.max_players as usize {
+            panic!("Tournament is full");
+        }
+        if amount < tournament.entry_fee.0 {
+            panic!("Entry fee is {} MNL yocto", tournament.entry_fee.0);
+        }
+        self.assert_monster_owner(&monster_id, account_id);
+        if tournament.entries.iter().any(|entry| &entry.account_id == account_id) {
+            panic!("You are already registered");
+        }
+
+        tournament.entries.push(TournamentEntry { account_id: account_id.to_string(), monster_id });
+        tournament.prize_pool = U128(tournament.prize_pool.0 + tournament.entry_fee.0);
+        self.tournaments.insert(&tournament_id, &tournament);
+
+        amount - tournament.entry_fee.0
+    }
+
+    // Close registration and seed players by rating
+    pub(crate) fn tournament_start(&mut self, tournament_id: u64) {
+        let mut tournament = self.tournaments.get(&tournament_id).expect("Tournament not found");
+        if tournament.status != TournamentStatus::Registration {
+            panic!("Tournament already started");
+        }
+        if tournament.entries.len() < 2 {
+            panic!("Tournament needs at least 2 players");
+        }
+
+        let mut players = tournament.entries.clone();
+        players.sort_by_key(|entry| std::cmp::Reverse(self.monster_rating(&entry.monster_id)));
+        tournament.players = players;
+        tournament.status = TournamentStatus::Running;
+        self.tournaments.insert(&tournament_id, &tournament);
+    }
+
+    // Cancel tournament before start, entry fees are returned to players
+    pub(crate) fn tournament_cancel(&mut self, tournament_id: u64) {
+        let mut tournament = self.tournaments.get(&tournament_id).expect("Tournament not found");
+        if tournament.status != TournamentStatus::Registration {
+            panic!("Only tournaments in registration can be cancelled");
+        }
+
+        for entry in tournament.entries.iter() {
+            self.pay_mnl_transfer(&entry.account_id, tournament.entry_fee.0);
+        }
+        tournament.prize_pool = U128(0);
+        tournament.status = TournamentStatus::Cancelled;
+        self.tournaments.insert(&tournament_id, &tournament);
+    }
+
+    // Resolve current round: best seed plays worst seed, middle seed of odd round gets a bye
+    pub(crate) fn tournament_advance(&mut self, account_id: &AccountId, tournament_id: u64) -> Vec<TournamentMatch> {
+        let mut tournament = self.tournaments.get(&tournament_id).expect("Tournament not found");
+        if tournament.status != TournamentStatus::Running {
+            panic!("Tournament is not running");
+        }
+        if account_id != &self.owner_id && !tournament.entries.iter().any(|entry| &entry.account_id == account_id) {
+            panic!("Only players can advance the tournament");
+        }
+
+        let mut random = self.random_stream(account_id, &format!("tournament-{}", tournament_id));
+        let players = tournament.players.clone();
+        let count = players.len();
+        let mut matches = vec![];
+        let mut losers = vec![];
+        for index in 0..count / 2 {
+            let first = players[index].clone();
+            let second = players[count - 1 - index].clone();
+            let first_wins = self.tournament_fight(&first, &second, &mut random);
+            let (winner, loser) = if first_wins { (first.clone(), second.clone()) } else { (second.clone(), first.clone()) };
+            losers.push(loser);
+            matches.push(TournamentMatch { first, second: Some(second), winner });
+        }
+        if count % 2 == 1 {
+            let first = players[count / 2].clone();
+            matches.push(TournamentMatch { first: first.clone(), second: None, winner: first });
+        }
+
+        // winners keep their seed order
+        tournament.players = players.iter()
+            .filter(|player| matches.iter().any(|game| game.winner.monster_id == player.monster_id))
+            .cloned()
+            .collect();
+        // players eliminated later get higher places, better seed first
+        losers.sort_by_key(|loser| players.iter().position(|player| player.monster_id == loser.monster_id));
+        losers.append(&mut tournament.standings);
+        tournament.standings = losers;
+        tournament.rounds.push(matches.clone());
+
+        if tournament.players.len() == 1 {
+            let mut standings = tournament.players.clone();
+            standings.append(&mut tournament.standings);
+            tournament.standings = standings;
+            tournament.status = TournamentStatus::Finished;
+            self.tournament_pay_prizes(tournament_id, &tournament);
+        }
+        self.tournaments.insert(&tournament_id, &tournament);
+
+        matches
+    }
+
+    // Monster which left its owner forfeits, ratings are updated only after a fight
+    fn tournament_fight(&mut self, first: &TournamentEntry, second: &TournamentEntry, random: &mut RandomStream) -> bool {
+        match (
+            self.monster_owned_by(&first.monster_id, &first.account_id),
+            self.monster_owned_by(&second.monster_id, &second.account_id),
+        ) {
+            (true, true) => self.pvp_fight(&first.monster_id, &second.monster_id, random),
+            (false, true) => false,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn tournaments_page(&self, from_index: Option<u64>, limit: Option<u64>) -> HashMap<u64, Tournament> {
+        self.tournaments.keys_as_vector().iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(TOURNAMENTS_DEFAULT_LIMIT) as usize)
+            .flat_map(|tournament_id| self.tournaments.get(&tournament_id).map(|tournament| (tournament_id, tournament)))
+            .collect()
+    }
+
+    fn tournament_pay_prizes(&mut self, tournament_id: u64, tournament: &Tournament) {
+        let mut prizes = vec![];
+        for (place, share) in tournament.prize_shares.iter().enumerate() {
+            if let Some(entry) = tournament.standings.get(place) {
+                let prize = tournament.prize_pool.0 * *share as u128 / 10_000;
+                if prize > 0 {
+                    self.pay_mnl_transfer(&entry.account_id, prize);
+                }
+                prizes.push(U128(prize));
+            }
+        }
+
+        EventLog::new(EventLogVariant::TournamentFinish(vec![TournamentFinishLog {
+            tournament_id,
+            standings: tournament.standings.iter().take(prizes.len()).cloned().collect(),
+            prizes,
+        }])).emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use crate::arena::ELO_DEFAULT_RATING;
+    use crate::monster::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn three_player_tournament() {
+        let mut contract = setup_contract();
+        let tournament_id = contract.create_tournament("Cup".to_string(), U128(100), 4, vec![7000, 2000]);
+
+        for account in 2..5 {
+            let monster_id = mint_test_monster(&mut contract, account);
+            set_context(account, 0);
+            let unused = contract.tournament_join(&accounts(account).into(), tournament_id, monster_id, 150);
+            assert_eq!(unused, 50);
+        }
+
+        set_context(1, 0);
+        contract.start_tournament(tournament_id);
+        assert_eq!(contract.get_tournament(tournament_id).prize_pool, U128(300));
+
+        set_context(2, 1);
+        let first_round = contract.advance_tournament(tournament_id);
+        assert_eq!(first_round.len(), 2);
+        assert!(first_round[1].second.is_none());
+
+        let final_round = contract.advance_tournament(tournament_id);
+        assert_eq!(final_round.len(), 1);
+
+        let tournament = contract.get_tournament(tournament_id);
+        assert_eq!(tournament.status, TournamentStatus::Finished);
+        assert_eq!(tournament.standings.len(), 3);
+        assert_eq!(tournament.standings[0].monster_id, final_round[0].winner.monster_id);
+    }
+
+    #[test]
+    fn gone_monster_forfeits_without_rating_change() {
+        let mut contract = setup_contract();
+        let tournament_id = contract.create_tournament("Cup".to_string(), U128(100), 2, vec![10_000]);
+        let monster = mint_test_monster(&mut contract, 2);
+        contract.tournament_join(&accounts(2).into(), tournament_id, monster.to_string(), 100);
+        let opponent_monster = mint_test_monster(&mut contract, 3);
+        contract.tournament_join(&accounts(3).into(), tournament_id, opponent_monster.to_string(), 100);
+
+        set_context(1, 0);
+        contract.start_tournament(tournament_id);
+        let gone_monster = contract.get_monster(monster.to_string());
+        contract.monster_unlink_from_user(&gone_monster);
+
+        set_context(3, 1);
+        let final_round = contract.advance_tournament(tournament_id);
+        assert_eq!(final_round[0].winner.monster_id, opponent_monster);
+        assert_eq!(contract.get_monster_rating(opponent_monster), ELO_DEFAULT_RATING);
+        assert_eq!(contract.get_monster_rating(monster), ELO_DEFAULT_RATING);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tournament registration is closed")]
+    fn join_started_tournament() {
+        let mut contract = setup_contract();
+        let tournament_id = contract.create_tournament("Cup".to_string(), U128(100), 4, vec![10_000]);
+        for account in 2..4 {
+            let monster_id = mint_test_monster(&mut contract, account);
+            contract.tournament_join(&accounts(account).into(), tournament_id, monster_id, 100);
+        }
+        set_context(1, 0);
+        contract.start_tournament(tournament_id);
+
+        let monster_id = mint_test_monster(&mut contract, 4);
+        contract.tournament_join(&accounts(4).into(), tournament_id, monster_id, 100);
+    }
+
+    #[test]
+    fn cancel_refunds_entries() {
+        let mut contract = setup_contract();
+        let tournament_id = contract.create_tournament("Cup".to_string(), U128(100), 4, vec![10_000]);
+        let monster_id = mint_test_monster(&mut contract, 2);
+        contract.tournament_join(&accounts(2).into(), tournament_id, monster_id, 100);
+
+        set_context(1, 0);
+        contract.cancel_tournament(tournament_id);
+        let tournament = contract.get_tournament(tournament_id);
+        assert_eq!(tournament.status, TournamentStatus::Cancelled);
+        assert_eq!(tournament.prize_pool, U128(0));
+        assert_eq!(contract.get_tournaments(None, None).len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+use near_sdk::PromiseResult;
+
+use crate::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MnlTreasury {
+    // MNL which can still be minted as game rewards
+    pub reserve: U128,
+    // Rewards owed to players after failed payouts
+    pub failed_payouts: U128,
+    // MNL held by the contract and owed to players after failed transfers
+    pub failed_transfers: U128,
+}
+
+// Part of ft_emission result of the token
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenEmission {
+    pub total_minted: U128,
+    pub total_cap: Option<U128>,
+    pub period_minted: U128,
+    pub period_cap: Option<U128>,
+    pub period_duration: U64,
+    pub period_started_at: U64,
+}
+
+// Copy of the token emission caps, kept up to date between syncs with the token
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+pub struct MnlReserve {
+    // MNL which can be minted before the total cap is reached
+    pub total: Balance,
+    // MNL which can be minted in the current period
+    pub period: Balance,
+    pub period_cap: Option<Balance>,
+    pub period_duration: u64,
+    // Token starts new period with the first mint after this time
+    pub period_ends_at: u64,
+    // Rewards which are being minted
+    pub minting: Balance,
+    // Reserve was read from the token at least once
+    pub synced: bool,
+}
+
+impl MnlReserve {
+    pub fn from_emission(emission: &TokenEmission, minting: Balance) -> Self {
+        let total = match emission.total_cap {
+            Some(cap) => cap.0.saturating_sub(emission.total_minted.0),
+            None => Balance::MAX,
+        };
+        let period = match emission.period_cap {
+            Some(cap) => cap.0.saturating_sub(emission.period_minted.0),
+            None => Balance::MAX,
+        };
+        Self {
+            // rewards in flight may not be counted by the token yet
+            total: total.saturating_sub(minting),
+            period: period.saturating_sub(minting),
+            period_cap: emission.period_cap.map(|cap| cap.0),
+            period_duration: emission.period_duration.0,
+            period_ends_at: emission.period_started_at.0 + emission.period_duration.0,
+            minting,
+            synced: true,
+        }
+    }
+
+    // MNL which can be minted now before one of the caps is reached
+    pub fn available(&self, timestamp: u64) -> Balance {
+        let period = match self.period_cap {
+            Some(cap) if timestamp >= self.period_ends_at => cap,
+            _ => self.period,
+        };
+        self.total.min(period)
+    }
+
+    pub fn take(&mut self, amount: Balance, timestamp: u64) {
+        if amount > self.available(timestamp) {
+            panic!("Not enough MNL reserve for reward");
+        }
+        if let Some(cap) = self.period_cap {
+            if timestamp >= self.period_ends_at {
+                self.period = cap;
+                self.period_ends_at = timestamp + self.period_duration;
+            }
+        }
+        self.total -= amount;
+        self.period = self.period.saturating_sub(amount);
+        self.minting += amount;
+    }
+
+    // Mint is finished, failed one is returned to the reserve
+    pub fn resolve(&mut self, amount: Balance, minted: bool) {
+        self.minting = self.minting.saturating_sub(amount);
+        if !minted {
+            self.total = self.total.saturating_add(amount);
+            self.period = self.period.saturating_add(amount);
+        }
+    }
+}
+
+impl Contract {
+    // Mint game reward from the reserve, failed payout is recorded in on_mnl_reward_paid
+    pub(crate) fn pay_mnl_reward(&mut self, account_id: &AccountId, amount: Balance, memo: &str) {
+        // reserve is unknown before the first sync, reward is kept for retry until the sync
+        if !self.mnl_reserve.synced {
+            self.mnl_reserve_sync();
+            self.add_failed_payout(account_id, amount);
+            return;
+        }
+
+        let period_rollover = self.mnl_reserve.period_cap.is_some()
+            && env::block_timestamp() >= self.mnl_reserve.period_ends_at;
+        self.mnl_reserve.take(amount, env::block_timestamp());
+        // correct local copy of the new period with the token state
+        if period_rollover {
+            self.mnl_reserve_sync();
+        }
+
+        self.mint_mnl(account_id, amount, memo).then(Promise::new(env::current_account_id()).function_call(
+            b"on_mnl_reward_paid".to_vec(),
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(5),
+        ));
+    }
+
+    pub(crate) fn resolve_mnl_reward(&mut self, account_id: &AccountId, amount: Balance) -> bool {
+        let minted = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        self.mnl_reserve.resolve(amount, minted);
+        if !minted {
+            self.add_failed_payout(account_id, amount);
+        }
+        minted
+    }
+
+    fn add_failed_payout(&mut self, account_id: &AccountId, amount: Balance) {
+        let owed = self.failed_payouts.get(account_id).unwrap_or(0);
+        self.failed_payouts.insert(account_id, &(owed + amount));
+        self.failed_payouts_total += amount;
+    }
+
+    // Transfer MNL held by the contract (stakes, entry fees), failed transfer is recorded in on_mnl_payout_transferred
+    pub(crate) fn pay_mnl_transfer(&mut self, account_id: &AccountId, amount: Balance) {
+        self.transfer_mnl(account_id, amount).then(Promise::new(env::current_account_id()).function_call(
+            b"on_mnl_payout_transferred".to_vec(),
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+            }).to_string().as_bytes().to_vec(),
+            0,
+            self.to_tera(5),
+        ));
+    }
+
+    pub(crate) fn resolve_mnl_transfer(&mut self, account_id: &AccountId, amount: Balance) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+
+        let owed = self.failed_transfers.get(account_id).unwrap_or(0);
+        self.failed_transfers.insert(account_id, &(owed + amount));
+        self.failed_transfers_total += amount;
+        false
+    }
+
+    // Pay all failed rewards of the account again
+    pub(crate) fn retry_mnl_rewards(&mut self, account_id: &AccountId) -> Balance {
+        let owed = self.failed_payouts.remove(account_id).unwrap_or(0);
+        if owed > 0 {
+            self.failed_payouts_total -= owed;
+            self.pay_mnl_reward(account_id, owed, "Reward retry");
+        }
+        owed
+    }
+
+    // Send all failed transfers of the account again
+    pub(crate) fn retry_mnl_transfers(&mut self, account_id: &AccountId) -> Balance {
+        let owed = self.failed_transfers.remove(account_id).unwrap_or(0);
+        if owed > 0 {
+            self.failed_transfers_total -= owed;
+            self.pay_mnl_transfer(account_id, owed);
+        }
+        owed
+    }
+
+    // Retry failed rewards and transfers of the account, returns paid amount
+    pub(crate) fn retry_payouts(&mut self, account_id: &AccountId) -> Balance {
+        let paid = self.retry_mnl_rewards(account_id) + self.retry_mnl_transfers(account_id);
+        if paid == 0 {
+            panic!("No failed payouts");
+        }
+        paid
+    }
+
+    pub(crate) fn mnl_reserve_sync(&self) -> Promise {
+        Promise::new(self.contract_ft.clone()).function_call(
+            b"ft_emission".to_vec(),
+            b"{}".to_vec(),
+            0,
+            self.to_tera(5),
+        ).then(Promise::new(env::current_account_id()).function_call(
+            b"on_mnl_reserve_synced".to_vec(),
+            b"{}".to_vec(),
+            0,
+            self.to_tera(5),
+        ))
+    }
+
+    // Update reserve from the token ft_emission result
+    pub(crate) fn resolve_mnl_reserve(&mut self) -> U128 {
+        if let PromiseResult::Successful(value) = env::promise_result(0) {
+            let emission: TokenEmission = near_sdk::serde_json::from_slice(&value).expect("Wrong emission");
+            self.mnl_reserve = MnlReserve::from_emission(&emission, self.mnl_reserve.minting);
+        }
+        self.mnl_reserve.available(env::block_timestamp()).into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{MockedBlockchain, RuntimeFeesConfig, testing_env, VMConfig};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+
+    use super::*;
+
+    fn set_callback_context(result: PromiseResult) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0));
+        testing_env!(builder.build(), VMConfig::free(), RuntimeFeesConfig::free(), Default::default(), vec![result]);
+    }
+
+    const EMISSION: &[u8] = br#"{"total_minted":"100","total_cap":"150","period_minted":"0","period_cap":null,"period_duration":"10","period_started_at":"0"}"#;
+
+    #[test]
+    #[should_panic(expected = "Not enough MNL reserve for reward")]
+    fn kill_rejected_without_reserve() {
+        set_callback_context(PromiseResult::Successful(EMISSION.to_vec()));
+        let mut contract = Contract::default();
+        assert_eq!(contract.on_mnl_reserve_synced().0, 50);
+
+        contract.pay_mnl_reward(&accounts(2).into(), 40, "Stone kill");
+        assert_eq!(contract.get_mnl_treasury().reserve.0, 10);
+        contract.pay_mnl_reward(&accounts(2).into(), 40, "Stone kill");
+    }
+
+    #[test]
+    fn reward_kept_until_first_sync() {
+        set_callback_context(PromiseResult::Successful(EMISSION.to_vec()));
+        let mut contract = Contract::default();
+        contract.pay_mnl_reward(&accounts(2).into(), 40, "Stone kill");
+        assert_eq!(contract.get_failed_payout(accounts(2)).0, 40);
+
+        contract.on_mnl_reserve_synced();
+        assert_eq!(contract.retry_payouts(&accounts(2).into()), 40);
+        assert_eq!(contract.get_mnl_treasury().reserve.0, 10);
+    }
+
+    #[test]
+    fn failed_transfer_recorded_for_retry() {
+        set_callback_context(PromiseResult::Failed);
+        let mut contract = Contract::default();
+        contract.pay_mnl_transfer(&accounts(2).into(), 40);
+        assert!(!contract.on_mnl_payout_transferred(accounts(2).into(), U128(40)));
+        assert_eq!(contract.get_mnl_treasury().failed_transfers.0, 40);
+        assert_eq!(contract.get_failed_payout(accounts(2)).0, 40);
+
+        assert_eq!(contract.retry_payouts(&accounts(2).into()), 40);
+        assert_eq!(contract.get_mnl_treasury().failed_transfers.0, 0);
+        // transfers come from the contract balance, not from the reserve
+        assert_eq!(contract.get_mnl_treasury().failed_payouts.0, 0);
+    }
+
+    #[test]
+    fn sync_keeps_rewards_in_flight() {
+        set_callback_context(PromiseResult::Successful(EMISSION.to_vec()));
+        let mut contract = Contract::default();
+        contract.on_mnl_reserve_synced();
+        contract.pay_mnl_reward(&accounts(2).into(), 40, "Stone kill");
+
+        // token doesn't know about the reward yet
+        assert_eq!(contract.on_mnl_reserve_synced().0, 10);
+    }
+
+    #[test]
+    fn period_cap_refills_on_rollover() {
+        let mut reserve = MnlReserve {
+            total: 1_000,
+            period: 0,
+            period_cap: Some(100),
+            period_duration: 10,
+            period_ends_at: 10,
+            minting: 0,
+            synced: true,
+        };
+        assert_eq!(reserve.available(5), 0);
+        assert_eq!(reserve.available(10), 100);
+
+        reserve.take(30, 12);
+        assert_eq!((reserve.period, reserve.period_ends_at), (70, 22));
+        reserve.resolve(30, true);
+        assert_eq!(reserve.available(21), 70);
+        assert_eq!(reserve.available(22), 100);
+    }
+
+    #[test]
+    fn failed_payout_recorded_for_retry() {
+        set_callback_context(PromiseResult::Failed);
+        let mut contract = Contract {
+            mnl_reserve: MnlReserve { total: 100, period: Balance::MAX, synced: true, ..Default::default() },
+            ..Default::default()
+        };
+        contract.pay_mnl_reward(&accounts(2).into(), 40, "Stone kill");
+        assert!(!contract.on_mnl_reward_paid(accounts(2).into(), U128(40)));
+        assert_eq!(contract.get_mnl_treasury().failed_payouts.0, 40);
+        assert_eq!(contract.get_mnl_treasury().reserve.0, 100);
+
+        assert_eq!(contract.retry_mnl_rewards(&accounts(2).into()), 40);
+        assert_eq!(contract.get_mnl_treasury().failed_payouts.0, 0);
+        assert_eq!(contract.get_mnl_treasury().reserve.0, 60);
+    }
+}
@@ -6,15 +6,6 @@ impl Contract {
         *env::random_seed().get(index).unwrap()
     }
 
-    // Get random number from 0 to max-1
-    pub(crate) fn random_u8_range(&self, index: usize, max: usize) -> u8 {
-        if max > 1 {
-            let rand_divider = 256 / u32::try_from(max).unwrap();
-            return self.random_u8(index) / u8::try_from(rand_divider).unwrap();
-        }
-        0.into()
-    }
-
     // Convert f64 to yocto NEAR Balance
     pub(crate) fn to_yocto(&self, value: &str) -> Balance {
         let values: Vec<_> = value.split('.').collect();
@@ -66,6 +66,11 @@ pub(crate) fn assert_at_least_one_yocto() {
 
 //refund the initial deposit based on the amount of storage that was used up
 pub(crate) fn refund_deposit(storage_used: u64) {
+    refund_deposit_to(storage_used, env::predecessor_account_id());
+}
+
+//refund the excess deposit to the given account (token owner who paid for storage through the parent contract)
+pub(crate) fn refund_deposit_to(storage_used: u64, refund_id: AccountId) {
     //get how much it would cost to store the information
     let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
     //get the attached deposit
@@ -81,9 +86,9 @@ pub(crate) fn refund_deposit(storage_used: u64) {
     //get the refund amount from the attached deposit - required cost
     let refund = attached_deposit - required_cost;
 
-    //if the refund is greater than 1 yocto NEAR, we refund the predecessor that amount
+    //if the refund is greater than 1 yocto NEAR, we refund that amount
     if refund > 1 {
-        Promise::new(env::predecessor_account_id()).transfer(refund);
+        Promise::new(refund_id).transfer(refund);
     }
 }
 
@@ -152,6 +157,9 @@ impl Contract {
         //get the token object by passing in the token_id
         let token = self.tokens_by_id.get(token_id).expect("No token");
 
+        //locked tokens can't be transferred until the parent contract unlocks them
+        assert!(!self.locked_tokens.contains(token_id), "Token is locked");
+
         //if the sender doesn't equal the owner, we check if the sender is in the approval list
         if sender_id != &token.owner_id {
             //if the token's approved account IDs doesn't contain the sender, we panic
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
@@ -28,6 +28,8 @@ mod events;
 pub const NFT_METADATA_SPEC: &str = "nft-1.0.0";
 /// This is the name of the NFT standard we're using
 pub const NFT_STANDARD_NAME: &str = "nep171";
+/// Collection badges are locked forever
+pub const BADGE_TOKEN_PREFIX: &str = "badge-";
 
 const TOKEN_IMAGE_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 512 512'%3E%3Cpath d='m92.8 325.1 2.1-19.1s-4.2-12.5 5-13.7 9.1-.8 26.1-3.7c17-2.9 12-3.3 28.2-7.1 16.2-3.7 3.7-6.6 19.1-5.4 15.4 1.2 64.8 3.3 70.2 4.2s7.5-2.1 7.5 1.2 5 8.7 23.3 10 12.9 3.3 21.2 1.2c8.3-2.1 26.6-4.2 33.6-1.2 7.1 2.9 17.4 12 31.1 12 13.7 0 24.3 5.8 28.8 14.9s2.3 4.2 8.5 8.3c6.2 4.2 17.8 1.7 23.2 10.8 5.4 9.1 6.6 11.6 0 19.5s-36.9 10.8-46.1 11.2-15.4-2.9-28.2 2.5c-12.9 5.4 19.5 5.8-19.1 5.4-38.6-.4-27.4 2.1-46.1-3.3-18.7-5.4-50.6-10.4-50.6-10.4s-81.4-19.1-85.5-20.3c-4.2-1.2-18.3-8.7-23.7-7.9-5.4.8-2.9 5.8-13.7.8-10.7-4.9-14.9-9.9-14.9-9.9z' fill='%23666'/%3E%3Cpath d='M114.9 350.4s-.6.6 5.4 2.6 5.5 1.4 8.9 2c3.4.6 4.2.3 8.7 3.8s2.6 1.4 8.4 2.5c5.7 1 19.2 15.1 47.8 17.2 28.6 2.1 23.7 1.3 34.7 2.5 11 1.2 8.2-2.9 27.4 5s16.8 5.8 27 8.7 40.4-3.8 47-3.8c6.5 0 3.3-6.2 15.9-1.7 12.7 4.6 18.8 6.2 22.9 6.2 4.1 0 7-5.4 15.5-7.9 8.6-2.5 18.8-10 24.9-11.2 6.1-1.2 9.8-7.9 9.8-7.9l7-13.4s2.5-1.1 2.3-2.5c-.2-1.4-5.4-6.7-5.4-6.7l-3-8.6-.6 1.9c3.2 6.1 3.2 8.9-2.3 15.5-.8 1-2.1 2-3.6 2.8-10.3 5.8-33.9 8-41.7 8.4-9 .4-15.1-2.9-27.8 2.5-8.6 3.7-2.8 5.1-6.3 5.5-1.6.2-.4 0-12.5-.1-38-.4-27 2.1-45.4-3.3S230 360 230 360s-80.1-19.1-84.2-20.3c-4.1-1.2-18-8.7-23.3-7.9-5.3.8-2.9 5.8-13.5.8s-14.7-10-14.7-10l2-19.1s-1-3.1-.9-6.4c-1.8 1.4-4.1 3.1-5.1 7.6-1 4.6-6.7 16.8-6.7 19.9 0 3.1 4.7 8.5 8 13.9s14.3 12.7 14.3 12.7l9-.8z' fill='%235c5c5c'/%3E%3Cpath d='M284 247.5s16.6-24.4 21.3-45.6c4.8-21.2 4.8-25.5 4.8-25.5l17.5-25.2c1.2-1.8 2.6-8.9-1.1-11.9-5-6.5-10.8-7.8-15.8-4.8-3.7 2.3-7.7 9.5-7.3 13l-.4 6.1c-9.6 10-8.8 10.4-12.7 14.2-1.5 1.4-5.9-2.5-5.8-2.4.5.6 4.2-14.5 4.2-22.4 0-7.9 13.2-23 16.9-31.9 3.7-9-8.8-19.6-18-8.2-9.2 11.4-13.9 25-13.9 25l-8.7 31.6-7-.4s-1.3-26.4-.4-31c.5-2.3 3.9-11.6 7-20.8 3-8.9 5.7-17.8 5.2-20.5-1.1-5.6-13.2-10.4-19.5-.8-6.4 9.5-12.7 46.4-13.2 48-.5 1.6-1.6 11.7-1.6 16.2s-.1 10.1-.1 10.1l-5.3-2.5c-3.6-1.7-3.2-8.5-3.9-15.6-.8-8.8-2-18.4.3-22.8 4.1-7.6 1.5-10.4-4.4-16.2-2.1-.1-12.7-3.3-16.5 3-4.8 6.9-3.2 12.1-2.9 17.9.6 10.5 3.7 20.5 3.7 28.9 0 1.2 1.7 6.2 3.3 11.2-3.6 2-8.1 4.3-11.3 5.5-6.4 2.4-7.2 5-7.2 7.9 0 2.9 3.7 16.7 4.2 25.7.4 7.2 10.4 15 17.2 19 6.1 15.2 12.4 10.3 14 21.3 2.5 16.4-2 48.6-6.2 79.9 9.2-2 17.6-2.2 24.1-.6 7.3 1.8 5.4 4.7 16.2-2.9s1-10.5 19.1-2.6l2.3 1 1.9-70.9z' fill='%23edd6c0'/%3E%3Cpath fill='%23999' d='M253 82.9c1.8-3.9 7.1-14.9 13.2-18 6.1-3 7.1 2.4 7.1 2.4-.4 3.3-3 13.1-4.3 17.4-2.6-3.9-10.3-6.4-16-1.8zM211.1 106.4s9.4 15.5 16.7 15c4-.3.1-9.2-1-10.9l-2.9-4.2c-4.7-1.3-9-1.5-12.8.1zM305.7 146.8l6.2-9.7c2.6-1.2 9.1.5 9.7 4.1.3 1.7-5 6.5-9.4 11.9-4.8 5.9-14.6 7.6-6.5-6.3zM292.9 98.6c3.4-4.2 8.5-9.5 13.1-10.8 6.5-1.8 6.6 3.7 6.6 3.7-.9 2.7-4.1 9.5-6.4 14-1.2-5.3-7.2-9.5-13.3-6.9z'/%3E%3Cpath d='M221.9 164s4.4 9.5 1.6 14.6c-2.8 5-9.5 6.8-12 8.5l1.5 3.2s27.9-4.5 36.9 13.4 9 18.1 9 18.1 7.8-18.5 13.4-27.3c5.7-8.8 8.4-11.3 8.4-11.3s-9 1.6-15.6-1c-6.6-2.5-7-3.7-13.4-3.3-5.1.3-16.8 7.3-25 1.2 2.1-10-2.5-14.7-4.8-16.1z' fill='%23ddc8b5'/%3E%3Cpath fill='%23999' d='M205.8 167.3s22.3-10.3 24.5-10.6 3.2 1.3.7 4.2-22.7 13.3-22.7 13.3-1.7-5.8-2.5-6.9z'/%3E%3C/svg%3E%0A";
 
@@ -38,6 +40,7 @@ pub struct StoneTokenData {
     token_id: TokenId,
     receiver_id: AccountId,
     token_metadata: TokenMetadata,
+    perpetual_royalties: Option<HashMap<AccountId, u32>>,
 }
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -66,6 +69,9 @@ pub struct Contract {
 
     //keeps track of the metadata for the contract
     pub metadata: LazyOption<NFTContractMetadata>,
+
+    //token IDs locked by the parent contract (staked, equipped etc), they can't be transferred
+    pub locked_tokens: LookupSet<TokenId>,
 }
 
 /// Helper structure for keys of the persistent collections.
@@ -79,6 +85,7 @@ pub enum StorageKey {
     TokensPerType,
     TokensPerTypeInner { token_type_hash: CryptoHash },
     TokenTypesLocked,
+    LockedTokens,
 }
 
 #[near_bindgen]
@@ -126,6 +133,7 @@ impl Contract {
                 StorageKey::NFTContractMetadata.try_to_vec().unwrap(),
                 Some(&metadata),
             ),
+            locked_tokens: LookupSet::new(StorageKey::LockedTokens.try_to_vec().unwrap()),
         };
 
         //return the Contract object
@@ -133,3 +141,148 @@ impl Contract {
     }
 
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::convert::TryFrom;
+
+    use near_sdk::{MockedBlockchain, testing_env};
+    use near_sdk::json_types::ValidAccountId;
+    use near_sdk::test_utils::VMContextBuilder;
+
+    use super::*;
+    use crate::nft_core::NonFungibleTokenCore;
+
+    const MAIN: &str = "main";
+    const ALICE: &str = "alice";
+
+    fn set_context(predecessor: &str, deposit: Balance) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(ValidAccountId::try_from("nft-stone.main").unwrap())
+            .predecessor_account_id(ValidAccountId::try_from(predecessor).unwrap())
+            .attached_deposit(deposit);
+        testing_env!(builder.build());
+    }
+
+    fn token_data(token_id: &str) -> StoneTokenData {
+        StoneTokenData {
+            token_id: token_id.to_string(),
+            receiver_id: ALICE.to_string(),
+            token_metadata: TokenMetadata {
+                title: Some(token_id.to_string()),
+                description: None,
+                media: None,
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                extra: None,
+                reference: None,
+                reference_hash: None,
+            },
+            perpetual_royalties: None,
+        }
+    }
+
+    // Contract with stones "1" and "2" of alice
+    fn setup() -> Contract {
+        set_context(MAIN, 0);
+        let mut contract = Contract::new_default_meta(MAIN.to_string());
+        set_context(MAIN, 10u128.pow(24));
+        contract.mint_multiple_stones(vec![token_data("1"), token_data("2")]);
+        contract
+    }
+
+    #[test]
+    fn lock_and_unlock_by_main() {
+        let mut contract = setup();
+        contract.nft_lock_tokens(vec!["1".to_string()], ALICE.to_string());
+        assert!(contract.nft_is_locked("1".to_string()));
+        assert!(!contract.nft_is_locked("2".to_string()));
+
+        set_context(MAIN, 0);
+        contract.nft_unlock_tokens(vec!["1".to_string()], ALICE.to_string());
+        assert!(!contract.nft_is_locked("1".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is locked")]
+    fn locked_token_transfer_rejected() {
+        let mut contract = setup();
+        contract.nft_lock_tokens(vec!["1".to_string()], ALICE.to_string());
+
+        set_context(ALICE, 1);
+        contract.nft_transfer("bob".to_string(), "1".to_string(), 0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "You can't mint NFT directly")]
+    fn unlock_only_by_main() {
+        let mut contract = setup();
+        contract.nft_lock_tokens(vec!["1".to_string()], ALICE.to_string());
+
+        set_context(ALICE, 0);
+        contract.nft_unlock_tokens(vec!["1".to_string()], ALICE.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token owner mismatch")]
+    fn lock_only_owner_tokens() {
+        let mut contract = setup();
+        contract.nft_lock_tokens(vec!["1".to_string()], "bob".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Badge can't be unlocked")]
+    fn badge_stays_locked() {
+        let mut contract = setup();
+        contract.mint_badge(token_data("badge-1-alice"));
+        assert!(contract.nft_is_locked("badge-1-alice".to_string()));
+
+        set_context(MAIN, 0);
+        contract.nft_unlock_tokens(vec!["badge-1-alice".to_string()], ALICE.to_string());
+    }
+
+    #[test]
+    fn update_extra() {
+        let mut contract = setup();
+        contract.nft_update_extra("1".to_string(), "{\"hardness\":3}".to_string());
+        let token = contract.nft_token("1".to_string()).unwrap();
+        assert_eq!(token.metadata.extra, Some("{\"hardness\":3}".to_string()));
+    }
+
+    #[test]
+    fn fused_stone_replaces_inputs() {
+        let mut contract = setup();
+        contract.mint_fused_stone(token_data("3"), vec!["1".to_string(), "2".to_string()]);
+        assert!(contract.nft_token("1".to_string()).is_none());
+        assert!(contract.nft_token("2".to_string()).is_none());
+        assert_eq!(contract.nft_token("3".to_string()).unwrap().owner_id, ALICE);
+    }
+
+    #[test]
+    fn dismantle_monster_into_stones() {
+        let mut contract = setup();
+        contract.mint_monster(MonsterTokenData {
+            token_id: "monster-1".to_string(),
+            receiver_id: ALICE.to_string(),
+            token_metadata: token_data("monster-1").token_metadata,
+            input_stones: vec!["1".to_string()],
+        });
+
+        contract.dismantle_monster("monster-1".to_string(), ALICE.to_string(), vec![token_data("3")]);
+        assert!(contract.nft_token("monster-1".to_string()).is_none());
+        assert_eq!(contract.nft_token("3".to_string()).unwrap().owner_id, ALICE);
+    }
+
+    #[test]
+    fn destroy_drops_lock() {
+        let mut contract = setup();
+        contract.nft_lock_tokens(vec!["1".to_string()], ALICE.to_string());
+        contract.nft_destroy("1".to_string(), &ALICE.to_string());
+        assert!(!contract.nft_is_locked("1".to_string()));
+    }
+}
@@ -14,7 +14,7 @@ impl Contract {
                 new_token.token_id,
                 new_token.token_metadata,
                 new_token.receiver_id,
-                None,
+                new_token.perpetual_royalties,
             );
         }
 
@@ -44,6 +44,69 @@ impl Contract {
         }
     }
 
+    #[payable]
+    pub fn dismantle_monster(&mut self, monster_id: TokenId, owner_id: AccountId, stones_metadata: Vec<StoneTokenData>) {
+        self.assert_parent_contract();
+        assert_eq!(self.tokens_by_id.get(&monster_id).expect("No token").owner_id, owner_id, "Token owner mismatch");
+
+        let initial_storage_usage = env::storage_usage();
+
+        for new_token in stones_metadata {
+            self.internal_mint(
+                new_token.token_id,
+                new_token.token_metadata,
+                new_token.receiver_id,
+                new_token.perpetual_royalties,
+            );
+        }
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit(required_storage_in_bytes);
+
+        self.nft_destroy(monster_id, &owner_id);
+    }
+
+    #[payable]
+    pub fn mint_fused_stone(&mut self, stone_data: StoneTokenData, input_stones: Vec<TokenId>) {
+        self.assert_parent_contract();
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.internal_mint(
+            stone_data.token_id,
+            stone_data.token_metadata,
+            stone_data.receiver_id.to_string(),
+            stone_data.perpetual_royalties,
+        );
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit(required_storage_in_bytes);
+
+        for stone in input_stones.iter() {
+            self.nft_destroy(stone.to_string(), &stone_data.receiver_id);
+        }
+    }
+
+    // Mint non-transferable badge (locked forever)
+    #[payable]
+    pub fn mint_badge(&mut self, badge_data: StoneTokenData) {
+        self.assert_parent_contract();
+        assert!(badge_data.token_id.starts_with(BADGE_TOKEN_PREFIX), "Wrong badge token id");
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.internal_mint(
+            badge_data.token_id.to_string(),
+            badge_data.token_metadata,
+            badge_data.receiver_id,
+            None,
+        );
+        self.locked_tokens.insert(&badge_data.token_id);
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit(required_storage_in_bytes);
+    }
+
     #[payable]
     pub fn nft_destroy(&mut self, token_id: TokenId, token_owner_id: &AccountId) {
         self.assert_parent_contract();
@@ -52,6 +115,7 @@ impl Contract {
 
         self.tokens_by_id.remove(&token_id);
         self.token_metadata_by_id.remove(&token_id);
+        self.locked_tokens.remove(&token_id);
 
         let mut token_ids = self.tokens_per_owner.get(token_owner_id).unwrap();
         token_ids.remove(&token_id);
@@ -65,6 +129,63 @@ impl Contract {
         }
     }
 
+    // Lock tokens of the owner, excess deposit is returned to the owner who paid it
+    #[payable]
+    pub fn nft_lock_tokens(&mut self, token_ids: Vec<TokenId>, owner_id: AccountId) {
+        self.assert_parent_contract();
+
+        let initial_storage_usage = env::storage_usage();
+
+        for token_id in token_ids.iter() {
+            assert_eq!(self.tokens_by_id.get(token_id).expect("No token").owner_id, owner_id, "Token owner mismatch");
+            self.locked_tokens.insert(token_id);
+        }
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit_to(required_storage_in_bytes, owner_id);
+    }
+
+    // Unlock tokens of the owner, released storage is returned to the owner
+    pub fn nft_unlock_tokens(&mut self, token_ids: Vec<TokenId>, owner_id: AccountId) {
+        self.assert_parent_contract();
+
+        let initial_storage_usage = env::storage_usage();
+
+        for token_id in token_ids.iter() {
+            assert!(!token_id.starts_with(BADGE_TOKEN_PREFIX), "Badge can't be unlocked");
+            assert_eq!(self.tokens_by_id.get(token_id).expect("No token").owner_id, owner_id, "Token owner mismatch");
+            self.locked_tokens.remove(token_id);
+        }
+
+        // return storage cost for the owner
+        let storage_usage_diff = initial_storage_usage - env::storage_usage();
+        if storage_usage_diff > 1 {
+            let return_cost = env::storage_byte_cost() * Balance::from(storage_usage_diff);
+            Promise::new(owner_id).transfer(return_cost);
+        }
+    }
+
+    // Update token `extra` metadata (stone attributes) from the parent contract
+    #[payable]
+    pub fn nft_update_extra(&mut self, token_id: TokenId, extra: String) {
+        self.assert_parent_contract();
+
+        let initial_storage_usage = env::storage_usage();
+
+        let mut metadata = self.token_metadata_by_id.get(&token_id).expect("No token");
+        metadata.extra = Some(extra);
+        metadata.updated_at = Some(env::block_timestamp() / 1_000_000);
+        self.token_metadata_by_id.insert(&token_id, &metadata);
+
+        let required_storage_in_bytes = env::storage_usage().saturating_sub(initial_storage_usage);
+        refund_deposit(required_storage_in_bytes);
+    }
+
+    //check if token is locked by the parent contract
+    pub fn nft_is_locked(&self, token_id: TokenId) -> bool {
+        self.locked_tokens.contains(&token_id)
+    }
+
     #[private]
     fn internal_mint(
         &mut self,
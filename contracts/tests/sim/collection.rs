@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use near_sdk::json_types::{U128};
 use near_sdk_sim::{call, to_yocto, view};
 
-use main::{Collection, MineType, Monster, Stone};
+use main::{JsonCollection, MineType, Monster, Stone};
 
 use crate::utils::{claim_mine, claim_stones, init};
 use nft_stone::JsonToken;
@@ -21,12 +21,12 @@ fn add_collection() {
     assert!(collection_add_result.is_ok());
 
     // Check all collections
-    let all_types: HashMap<String, Collection> = view!(
-        main_contract.get_collections()
+    let all_types: Vec<JsonCollection> = view!(
+        main_contract.get_collections(None, None)
     ).unwrap_json();
     assert_eq!(all_types.len(), 2);
 
-    let stone_images = &all_types.get("1").unwrap().stone_images;
+    let stone_images = &all_types[0].collection.stone_images;
     assert_eq!(stone_images.len(), 3);
 }
 
@@ -37,7 +37,7 @@ fn user_collection_counts() {
     claim_stones(&main_contract, &alice, mine.token_id.to_string(), mine.mine_type);
 
     let collection_counts: HashMap<u32, u32> = view!(
-        main_contract.user_collection_counts(alice.account_id)
+        main_contract.user_collection_counts(alice.account_id, None, None)
     ).unwrap_json();
 
     let collection_total = collection_counts.get(&1).unwrap();
@@ -84,6 +84,10 @@ pub fn init() -> (
     );
     assert!(collection_add_result.is_ok());
 
+    // Kill rewards are limited by MNL reserve read from the token
+    let sync_result = call!(root, main_contract.sync_mnl_reserve());
+    assert!(sync_result.is_ok());
+
     (root, main_contract, ft_contract, mine_contract, stone_contract, alice)
 }
 
@@ -13,3 +13,6 @@ near deploy --accountId nft-stone."$CONTRACT_ID" --wasmFile out/nft-stone.wasm
 
 near create-account ft."$CONTRACT_ID" --masterAccount "$CONTRACT_ID" --initialBalance 5
 near deploy --accountId ft."$CONTRACT_ID" --wasmFile out/ft.wasm
+
+# Init contracts, seed data & sync MNL reserve (kills are rejected until it's synced)
+echo "Run ./bootstrap.sh to init contracts"
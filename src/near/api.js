@@ -35,6 +35,23 @@ export const convertToTera = (amount) => {
     .toFixed();
 };
 
+const COLLECTIONS_PAGE_LIMIT = 50;
+
+// Load all collections from the paginated get_collections
+export const getAllCollections = async (contract) => {
+  let collections = [];
+  while (true) {
+    const page = await contract.get_collections({
+      from_index: collections.length,
+      limit: COLLECTIONS_PAGE_LIMIT,
+    });
+    collections = [...collections, ...page];
+    if (page.length < COLLECTIONS_PAGE_LIMIT) {
+      return collections;
+    }
+  }
+};
+
 export async function initContract() {
   const near = await connect(
     Object.assign(
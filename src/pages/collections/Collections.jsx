@@ -9,7 +9,7 @@ import { InnerPageHead } from "components/InnerPageHead";
 import { Loader } from "components/basic/Loader";
 import { CollectionContent } from "utils/content";
 import { Footer } from "components/Footer";
-import { getMedia } from "near/api";
+import { getAllCollections, getMedia } from "near/api";
 
 export const Collections = ({ currentUser, contract }) => {
   const [allCollections, setAllCollections] = useState([]);
@@ -18,13 +18,13 @@ export const Collections = ({ currentUser, contract }) => {
 
   useEffect(() => {
     const allCollectionsPromise = new Promise(async (resolve, reject) => {
-      const collections = await contract
-        .get_collections()
-        .catch((err) => reject(err));
-      const collection_list = Object.keys(collections).map((key) => {
+      const collections = await getAllCollections(contract).catch((err) =>
+        reject(err)
+      );
+      const collection_list = collections.map((collection) => {
         return {
-          id: key,
-          ...collections[key],
+          id: collection.collection_id,
+          ...collection,
         };
       });
       resolve(collection_list);
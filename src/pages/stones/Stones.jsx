@@ -4,6 +4,7 @@ import {
   convertFromYocto,
   convertToTera,
   convertToYocto,
+  getAllCollections,
 } from "near/api";
 import {
   Container,
@@ -69,9 +70,15 @@ export const Stones = ({ currentUser, contract, sellList, setSellList }) => {
   }
 
   async function fetchCollections() {
-    setAllCollections(await contract.get_collections());
+    setAllCollections(await getAllCollections(contract));
   }
 
+  const collectionTitle = (collectionId) => {
+    return allCollections.find(
+      (collection) => collection.collection_id === Number(collectionId)
+    )?.title;
+  };
+
   const appendToSellList = (stone) => {
     if (
       !sellList["stones"].filter((exist) => exist.token_id === stone.token_id)
@@ -215,10 +222,10 @@ export const Stones = ({ currentUser, contract, sellList, setSellList }) => {
   };
 
   const collectionOptions = () => {
-    const collections = Object.keys(allCollections).map((key) => {
+    const collections = allCollections.map((collection) => {
       return {
-        title: allCollections[key].title,
-        onClick: () => setFilterCollection(key),
+        title: collection.title,
+        onClick: () => setFilterCollection(collection.collection_id),
       };
     });
     return [
@@ -296,7 +303,7 @@ export const Stones = ({ currentUser, contract, sellList, setSellList }) => {
                         title="Collection"
                         selected={
                           filterCollection
-                            ? allCollections[filterCollection]?.title
+                            ? collectionTitle(filterCollection)
                             : null
                         }
                         options={collectionOptions()}
@@ -335,7 +342,7 @@ export const Stones = ({ currentUser, contract, sellList, setSellList }) => {
                       You don't have{" "}
                       <span className="ml-1 mr-2">{filterRarity}</span>
                       {filterCollection
-                        ? allCollections[filterCollection].title
+                        ? collectionTitle(filterCollection)
                         : ""}{" "}
                       Stones.
                     </div>